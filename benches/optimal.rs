@@ -1,16 +1,90 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use tictactoelib::alphabeta::{AlphaBetaPlayer, LineEvaluator};
 use tictactoelib::board::{Board, Position, Token};
+use tictactoelib::mcts::{Budget, MctsPlayer};
 use tictactoelib::optimal::OptimalPlayer;
 use tictactoelib::player::Player;
 
 pub fn optimal_player_benchmark(c: &mut Criterion) {
-    let player = OptimalPlayer {};
     let mut board = Board::new();
     board.add_move(Token::X, Position::new(1, 1)); // center
+
+    // A new player for each iteration, so that every move is solved from an empty table.
     c.bench_function("optimal_player_turn_2", |b| {
+        b.iter_batched(
+            OptimalPlayer::new,
+            |mut player| player.take_turn(black_box(&board)),
+            BatchSize::SmallInput,
+        )
+    });
+
+    // One player for every iteration, which only looks up what it solved the first time.
+    let mut player = OptimalPlayer::new();
+    c.bench_function("optimal_player_turn_2_cached", |b| {
+        b.iter(|| player.take_turn(black_box(&board)))
+    });
+}
+
+pub fn mcts_player_benchmark(c: &mut Criterion) {
+    let mut player = MctsPlayer::with_seed(Budget::Iterations(1000), 0);
+    let mut board = Board::new();
+    board.add_move(Token::X, Position::new(1, 1)); // center
+    c.bench_function("mcts_player_turn_2", |b| {
+        b.iter(|| player.take_turn(black_box(&board)))
+    });
+
+    let mut player = MctsPlayer::with_seed(Budget::Iterations(100), 0);
+    let mut gomoku = Board::with_size(15, 15, 5);
+    gomoku.add_move(Token::X, Position::new(7, 7));
+    c.bench_function("mcts_player_15x15", |b| {
+        b.iter(|| player.take_turn(black_box(&gomoku)))
+    });
+}
+
+pub fn alpha_beta_player_benchmark(c: &mut Criterion) {
+    let mut player = AlphaBetaPlayer::new(LineEvaluator);
+    let mut board = Board::new();
+    board.add_move(Token::X, Position::new(1, 1)); // center
+    c.bench_function("alpha_beta_player_turn_2", |b| {
         b.iter(|| player.take_turn(black_box(&board)))
     });
+
+    let mut player = AlphaBetaPlayer::new(LineEvaluator);
+    player.set_max_depth(2);
+    let mut gomoku = Board::with_size(15, 15, 5);
+    gomoku.add_move(Token::X, Position::new(7, 7));
+    c.bench_function("alpha_beta_player_15x15", |b| {
+        b.iter(|| player.take_turn(black_box(&gomoku)))
+    });
+}
+
+pub fn board_benchmark(c: &mut Criterion) {
+    let board: Board = "XO-\
+                        -X-\
+                        O--"
+    .parse()
+    .unwrap();
+    c.bench_function("get_game_result", |b| {
+        b.iter(|| black_box(&board).get_game_result())
+    });
+    c.bench_function("empty_positions", |b| {
+        b.iter(|| black_box(&board).empty_positions())
+    });
+    c.bench_function("empty_positions_iter", |b| {
+        b.iter(|| black_box(&board).empty_positions_iter().count())
+    });
+
+    let gomoku = Board::with_size(15, 15, 5);
+    c.bench_function("get_game_result_15x15", |b| {
+        b.iter(|| black_box(&gomoku).get_game_result())
+    });
 }
 
-criterion_group!(benches, optimal_player_benchmark);
+criterion_group!(
+    benches,
+    optimal_player_benchmark,
+    mcts_player_benchmark,
+    alpha_beta_player_benchmark,
+    board_benchmark
+);
 criterion_main!(benches);
@@ -0,0 +1,445 @@
+//! An alpha-beta search player, with iterative deepening and a time limit, for larger boards.
+//!
+//! The search looks one move further ahead on each iteration, and tries the best move of the
+//! previous iteration first, so that alpha-beta pruning cuts off as much as possible.
+//! Positions at the search horizon are scored by a pluggable Evaluator.
+//! If a deadline passes, the best move of the deepest completed iteration is played.
+use crate::board::{Board, GameResult, Position, Token};
+use crate::player::Player;
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// The score of a won game, less the number of moves it takes, so that faster wins score higher.
+/// Evaluations of unfinished games should be much smaller.
+pub const WIN_SCORE: i32 = 1_000_000;
+
+/// Scores a board whose game is not over, from one player's point of view.
+pub trait Evaluator {
+    /// Returns how good the board is for the player: positive if they are ahead,
+    /// negative if they are behind, and well within plus or minus WIN_SCORE.
+    fn evaluate(&self, board: &Board, player: Token) -> i32;
+}
+
+/// Any function of a board and a player can be used as an Evaluator.
+impl<F: Fn(&Board, Token) -> i32> Evaluator for F {
+    fn evaluate(&self, board: &Board, player: Token) -> i32 {
+        self(board, player)
+    }
+}
+
+/// Scores a board by the lines each player could still complete:
+/// each open line is worth ten times more for each token already on it.
+///
+/// # Examples
+/// ```
+/// use tictactoelib::alphabeta::{Evaluator, LineEvaluator};
+/// use tictactoelib::board::{Board, Token};
+///
+/// let board: Board = "---/-X-/---".parse().unwrap();
+/// // X has four open lines through the center, and O has none left through it.
+/// assert_eq!(LineEvaluator.evaluate(&board, Token::X), 40);
+/// assert_eq!(LineEvaluator.evaluate(&board, Token::O), -40);
+/// ```
+#[derive(Copy, Clone, Debug, Default)]
+pub struct LineEvaluator;
+
+impl LineEvaluator {
+    fn score_lines(board: &Board, token: Token) -> i32 {
+        board
+            .open_lines(token)
+            .iter()
+            .map(|line| {
+                let count = line
+                    .positions()
+                    .filter(|position| board.token_at(*position) == Some(token))
+                    .count();
+                match count {
+                    0 => 0,
+                    count => 10i32.saturating_pow(count as u32).min(WIN_SCORE / 1000),
+                }
+            })
+            .sum()
+    }
+}
+
+impl Evaluator for LineEvaluator {
+    fn evaluate(&self, board: &Board, player: Token) -> i32 {
+        LineEvaluator::score_lines(board, player)
+            - LineEvaluator::score_lines(board, player.opponent())
+    }
+}
+
+/// What an AlphaBetaPlayer's last search did.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchStatistics {
+    /// The number of positions visited, in every iteration.
+    pub nodes: u64,
+    /// The number of times a position's remaining moves were pruned.
+    pub cutoffs: u64,
+    /// The depth of the deepest iteration that was completed.
+    pub depth: u8,
+    /// The score of the chosen move, for the player making it.
+    pub score: i32,
+    /// Whether the search saw every position to the end of the game, so the score is exact.
+    pub complete: bool,
+    /// How long the search took.
+    pub elapsed: Duration,
+}
+
+/// A player that searches ahead with alpha-beta pruning, deepening until it runs out of
+/// moves to look at, reaches its depth limit, or runs out of time.
+///
+/// # Examples
+/// ```
+/// use tictactoelib::alphabeta::{AlphaBetaPlayer, LineEvaluator};
+/// use tictactoelib::board::{Board, Position};
+/// use tictactoelib::player::Player;
+///
+/// let mut player = AlphaBetaPlayer::new(LineEvaluator);
+/// let board: Board = "XX-/OO-/---".parse().unwrap();
+/// assert_eq!(player.take_turn(&board), Position::new(0, 2));
+/// assert!(player.last_search().complete);
+/// ```
+pub struct AlphaBetaPlayer {
+    evaluator: Box<dyn Evaluator>,
+    max_depth: Option<u8>,
+    time_limit: Option<Duration>,
+    statistics: SearchStatistics,
+}
+
+/// The state of one search.
+struct Search<'a> {
+    evaluator: &'a dyn Evaluator,
+    deadline: Option<Instant>,
+    /// The best move found for each position, by earlier iterations, to be tried first.
+    best_moves: HashMap<Board, Position>,
+    nodes: u64,
+    cutoffs: u64,
+    /// Whether any position was scored by the evaluator, rather than played out.
+    reached_horizon: bool,
+    out_of_time: bool,
+}
+
+impl AlphaBetaPlayer {
+    /// Creates a player that scores unfinished games with the given evaluator,
+    /// and searches without a depth or time limit.
+    pub fn new(evaluator: impl Evaluator + 'static) -> AlphaBetaPlayer {
+        AlphaBetaPlayer {
+            evaluator: Box::new(evaluator),
+            max_depth: None,
+            time_limit: None,
+            statistics: SearchStatistics::default(),
+        }
+    }
+
+    /// Limits how many moves ahead the player looks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the depth is zero.
+    pub fn set_max_depth(&mut self, depth: u8) {
+        if depth == 0 {
+            panic!("The search depth must be at least 1!");
+        }
+        self.max_depth = Some(depth);
+    }
+
+    /// Limits how long the player searches for each move.
+    /// The search always completes a depth of one, however long that takes.
+    pub fn set_time_limit(&mut self, time_limit: Duration) {
+        self.time_limit = Some(time_limit);
+    }
+
+    /// Returns what the player's last search did.
+    pub fn last_search(&self) -> SearchStatistics {
+        self.statistics
+    }
+
+    fn search(&mut self, board: &Board) -> Position {
+        let start = Instant::now();
+        let player = board.whose_turn().expect("Game should not be over!");
+        let empty_count = board.empty_positions_iter().count();
+        let max_depth = self
+            .max_depth
+            .map_or(empty_count, |depth| (depth as usize).min(empty_count));
+
+        let mut search = Search {
+            evaluator: self.evaluator.as_ref(),
+            deadline: self.time_limit.map(|limit| start + limit),
+            best_moves: HashMap::new(),
+            nodes: 0,
+            cutoffs: 0,
+            reached_horizon: false,
+            out_of_time: false,
+        };
+        let mut best: Option<(Position, i32)> = None;
+        let mut statistics = SearchStatistics::default();
+        for depth in 1..=max_depth {
+            search.reached_horizon = false;
+            // The first iteration always completes, so that there is a move to play.
+            let deadline = search.deadline;
+            if depth == 1 {
+                search.deadline = None;
+            }
+            let result = search.root(board, player, depth as u8);
+            search.deadline = deadline;
+            match result {
+                Some(result) => best = Some(result),
+                None => break,
+            }
+            statistics.depth = depth as u8;
+            if !search.reached_horizon {
+                statistics.complete = true;
+                break;
+            }
+        }
+
+        let (position, score) = best.expect("The first iteration always completes!");
+        statistics.nodes = search.nodes;
+        statistics.cutoffs = search.cutoffs;
+        statistics.score = score;
+        statistics.elapsed = start.elapsed();
+        self.statistics = statistics;
+        position
+    }
+}
+
+impl<'a> Search<'a> {
+    /// Searches every move from the root to the given depth, and returns the best with its score,
+    /// or None if the deadline passed first.
+    fn root(&mut self, board: &Board, player: Token, depth: u8) -> Option<(Position, i32)> {
+        self.nodes += 1;
+        let mut best: Option<(Position, i32)> = None;
+        let mut alpha = -WIN_SCORE - 1;
+        for position in self.ordered_moves(board) {
+            let mut next = *board;
+            next.add_move(player, position);
+            let score = -self.negamax(
+                &next,
+                player.opponent(),
+                depth - 1,
+                1,
+                -WIN_SCORE - 1,
+                -alpha,
+            )?;
+            if best.map_or(true, |(_position, best)| score > best) {
+                best = Some((position, score));
+                alpha = score;
+            }
+        }
+        let best = best.expect("There are no moves to make!");
+        self.best_moves.insert(*board, best.0);
+        Some(best)
+    }
+
+    /// Returns the score of the board for the player to move, or None if the deadline passed.
+    fn negamax(
+        &mut self,
+        board: &Board,
+        player: Token,
+        depth: u8,
+        ply: i32,
+        mut alpha: i32,
+        beta: i32,
+    ) -> Option<i32> {
+        self.nodes += 1;
+        if let Some(deadline) = self.deadline {
+            if self.nodes % 256 == 0 && Instant::now() >= deadline {
+                self.out_of_time = true;
+            }
+        }
+        if self.out_of_time {
+            return None;
+        }
+
+        match board.get_game_result() {
+            GameResult::Win(winner) if winner == player => return Some(WIN_SCORE - ply),
+            GameResult::Win(_winner) => return Some(ply - WIN_SCORE),
+            GameResult::Draw => return Some(0),
+            GameResult::InProgress => {}
+        }
+        if depth == 0 {
+            self.reached_horizon = true;
+            return Some(self.evaluator.evaluate(board, player));
+        }
+
+        let mut best_score = -WIN_SCORE - 1;
+        let mut best_move = None;
+        for position in self.ordered_moves(board) {
+            let mut next = *board;
+            next.add_move(player, position);
+            let score =
+                -self.negamax(&next, player.opponent(), depth - 1, ply + 1, -beta, -alpha)?;
+            if score > best_score {
+                best_score = score;
+                best_move = Some(position);
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                self.cutoffs += 1;
+                break;
+            }
+        }
+        if let Some(best_move) = best_move {
+            self.best_moves.insert(*board, best_move);
+        }
+        Some(best_score)
+    }
+
+    /// Returns the empty positions in the order to search them: the best move found by an
+    /// earlier iteration first, then the center, then the corners, then the rest.
+    fn ordered_moves(&self, board: &Board) -> Vec<Position> {
+        let mut moves = board.empty_positions();
+        let previous_best = self.best_moves.get(board).copied();
+        moves.sort_by_key(|position| {
+            if Some(*position) == previous_best {
+                0
+            } else {
+                square_priority(*position, board)
+            }
+        });
+        moves
+    }
+}
+
+/// Ranks a square by how many lines usually pass through it: the center, then the corners.
+fn square_priority(position: Position, board: &Board) -> u8 {
+    let is_middle = |value: u8, size: u8| value == (size - 1) / 2 || value == size / 2;
+    let is_end = |value: u8, size: u8| value == 0 || value == size - 1;
+    let (row, column) = (position.row(), position.column());
+    if is_middle(row, board.rows()) && is_middle(column, board.columns()) {
+        1
+    } else if is_end(row, board.rows()) && is_end(column, board.columns()) {
+        2
+    } else {
+        3
+    }
+}
+
+impl Player for AlphaBetaPlayer {
+    fn take_turn(&mut self, board: &Board) -> Position {
+        self.search(board)
+    }
+}
+
+#[cfg(test)]
+mod alphabeta_tests {
+    use super::*;
+    use crate::optimal::OptimalPlayer;
+    use crate::verification;
+
+    #[test]
+    fn test_never_loses() {
+        let verification = verification::verify(AlphaBetaPlayer::new(LineEvaluator));
+        assert!(verification.never_loses(), "{}", verification);
+    }
+
+    #[test]
+    fn test_complete_search_agrees_with_optimal_player() {
+        let mut player = AlphaBetaPlayer::new(LineEvaluator);
+        let mut optimal = OptimalPlayer::new();
+        let board = Board::from_string(
+            "X--\
+             -O-\
+             --X",
+        );
+        let position = player.take_turn(&board);
+        let statistics = player.last_search();
+        assert!(statistics.complete);
+        assert_eq!(statistics.score, 0);
+        let best = optimal
+            .evaluate_moves(&board)
+            .into_iter()
+            .find(|(candidate, _evaluation)| *candidate == position)
+            .unwrap();
+        assert_eq!(best.1.score(), 0);
+    }
+
+    #[test]
+    fn test_pruning() {
+        let mut player = AlphaBetaPlayer::new(LineEvaluator);
+        player.take_turn(&Board::new());
+        let statistics = player.last_search();
+        assert!(statistics.complete);
+        assert_eq!(statistics.depth, 9);
+        assert_eq!(statistics.score, 0);
+        assert_gt!(statistics.cutoffs, 0);
+        // Far fewer than the 549,946 positions of the full game tree, even searched nine times.
+        assert_lt!(statistics.nodes, 100_000);
+    }
+
+    #[test]
+    fn test_fastest_win() {
+        let mut player = AlphaBetaPlayer::new(LineEvaluator);
+        let board = Board::from_string(
+            "X-O\
+             ---\
+             X-O",
+        );
+        // Winning at once beats blocking O.
+        assert_eq!(player.take_turn(&board), Position::new(1, 0));
+        assert_eq!(player.last_search().score, WIN_SCORE - 1);
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let mut player = AlphaBetaPlayer::new(LineEvaluator);
+        player.set_max_depth(2);
+        let board = Board::from_string(
+            "XX-\
+             O--\
+             ---",
+        );
+        assert_eq!(player.take_turn(&board), Position::new(0, 2));
+        let statistics = player.last_search();
+        assert_eq!(statistics.depth, 2);
+        assert!(!statistics.complete);
+    }
+
+    #[test]
+    fn test_time_limit() {
+        let mut player = AlphaBetaPlayer::new(LineEvaluator);
+        player.set_time_limit(Duration::from_millis(50));
+        let mut board = Board::with_size(9, 9, 4);
+        board.add_move(Token::X, Position::new(4, 4));
+        let position = player.take_turn(&board);
+        assert!(board.is_position_unused(position));
+        let statistics = player.last_search();
+        assert!(!statistics.complete);
+        assert_gt!(statistics.depth, 0);
+        assert_lt!(statistics.elapsed, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_custom_evaluator() {
+        // An evaluator that likes the top left corner, and nothing else.
+        let corner = |board: &Board, player: Token| match board.token_at(Position::new(0, 0)) {
+            Some(token) if token == player => 1,
+            Some(_token) => -1,
+            None => 0,
+        };
+        let mut player = AlphaBetaPlayer::new(corner);
+        player.set_max_depth(1);
+        assert_eq!(player.take_turn(&Board::new()), Position::new(0, 0));
+    }
+
+    #[test]
+    fn test_misere() {
+        let mut player = AlphaBetaPlayer::new(LineEvaluator);
+        let mut board = Board::from_string(
+            "XX-\
+             OO-\
+             ---",
+        );
+        board.set_rules(crate::board::Rules::Misere);
+        assert_ne!(player.take_turn(&board), Position::new(0, 2));
+        assert!(player.last_search().complete);
+    }
+
+    #[test]
+    #[should_panic(expected = "The search depth must be at least 1!")]
+    fn test_zero_depth() {
+        AlphaBetaPlayer::new(LineEvaluator).set_max_depth(0);
+    }
+} // mod alphabeta_tests
@@ -0,0 +1,465 @@
+//! Round-robin tournaments between players, for comparing strategies.
+//!
+//! Every pair of entrants plays a match of several games, taking turns at playing X.
+//! The results are collected into standings, with Elo ratings, which can be printed
+//! as a text table or exported as CSV or JSON.
+use crate::board::{GameResult, Token};
+use crate::game::Game;
+use crate::player::Player;
+
+/// The rating every entrant starts with.
+pub const INITIAL_RATING: f64 = 1500.0;
+
+/// How far a single game can move a rating.
+pub const K_FACTOR: f64 = 16.0;
+
+type Factory = Box<dyn Fn() -> Box<dyn Player>>;
+
+struct Entrant {
+    name: String,
+    factory: Factory,
+}
+
+/// Plays a round-robin tournament between named players.
+///
+/// # Examples
+/// ```
+/// use tictactoelib::arena::Arena;
+/// use tictactoelib::optimal::OptimalPlayer;
+/// use tictactoelib::strategies::RandomPlayer;
+///
+/// let mut arena = Arena::new(10);
+/// arena.add("optimal", OptimalPlayer::new);
+/// arena.add("random", || RandomPlayer::with_seed(1));
+/// let standings = arena.run();
+/// assert_eq!(standings.iter().next().unwrap().name(), "optimal");
+/// ```
+pub struct Arena {
+    entrants: Vec<Entrant>,
+    games_per_match: u32,
+}
+
+impl Arena {
+    /// Creates an empty arena, where each pair of entrants will play the given number of games.
+    pub fn new(games_per_match: u32) -> Arena {
+        Arena {
+            entrants: Vec::new(),
+            games_per_match,
+        }
+    }
+
+    /// Adds an entrant to the tournament.
+    ///
+    /// The factory is called once at the start of each match, so that players which
+    /// remember (or learn) things keep them between the games of a match.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is already an entrant with the same name.
+    pub fn add<P, F>(&mut self, name: &str, factory: F)
+    where
+        P: Player + 'static,
+        F: Fn() -> P + 'static,
+    {
+        if self.entrants.iter().any(|entrant| entrant.name == name) {
+            panic!("Duplicate entrant: {}", name);
+        }
+        self.entrants.push(Entrant {
+            name: String::from(name),
+            factory: Box::new(move || Box::new(factory())),
+        });
+    }
+
+    /// Plays every match, and returns the standings, best first.
+    ///
+    /// Nothing is printed while the games are played.
+    pub fn run(&self) -> Standings {
+        let mut standings: Vec<Standing> = self
+            .entrants
+            .iter()
+            .map(|entrant| Standing::new(&entrant.name))
+            .collect();
+
+        for first in 0..self.entrants.len() {
+            for second in first + 1..self.entrants.len() {
+                let mut a = (self.entrants[first].factory)();
+                let mut b = (self.entrants[second].factory)();
+                for game in 0..self.games_per_match {
+                    // The entrants take turns at playing X, which has the advantage.
+                    let (x, o) = if game % 2 == 0 {
+                        (first, second)
+                    } else {
+                        (second, first)
+                    };
+                    let result = if x == first {
+                        Game::new(&mut a, &mut b).result()
+                    } else {
+                        Game::new(&mut b, &mut a).result()
+                    };
+                    record_game(&mut standings, x, o, &result);
+                }
+            }
+        }
+
+        standings.sort_by(|a, b| {
+            b.points()
+                .cmp(&a.points())
+                .then(b.rating.partial_cmp(&a.rating).unwrap())
+        });
+        Standings { standings }
+    }
+}
+
+fn record_game(standings: &mut [Standing], x: usize, o: usize, result: &GameResult) {
+    let x_score = match result {
+        GameResult::Win(Token::X) => {
+            standings[x].as_x.wins += 1;
+            standings[o].as_o.losses += 1;
+            1.0
+        }
+        GameResult::Win(Token::O) => {
+            standings[x].as_x.losses += 1;
+            standings[o].as_o.wins += 1;
+            0.0
+        }
+        GameResult::Draw => {
+            standings[x].as_x.draws += 1;
+            standings[o].as_o.draws += 1;
+            0.5
+        }
+        GameResult::InProgress => panic!("Game should be over!"),
+    };
+
+    let expected = 1.0 / (1.0 + 10f64.powf((standings[o].rating - standings[x].rating) / 400.0));
+    let change = K_FACTOR * (x_score - expected);
+    standings[x].rating += change;
+    standings[o].rating -= change;
+}
+
+/// The wins, draws and losses of an entrant.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Record {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Record {
+    /// Returns the number of games played.
+    pub fn games(self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+}
+
+impl std::ops::Add for Record {
+    type Output = Record;
+
+    fn add(self, other: Record) -> Record {
+        Record {
+            wins: self.wins + other.wins,
+            draws: self.draws + other.draws,
+            losses: self.losses + other.losses,
+        }
+    }
+}
+
+impl std::fmt::Display for Record {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}/{}/{}", self.wins, self.draws, self.losses)
+    }
+}
+
+/// How one entrant did in a tournament.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Standing {
+    name: String,
+    as_x: Record,
+    as_o: Record,
+    rating: f64,
+}
+
+impl Standing {
+    fn new(name: &str) -> Standing {
+        Standing {
+            name: String::from(name),
+            as_x: Record::default(),
+            as_o: Record::default(),
+            rating: INITIAL_RATING,
+        }
+    }
+
+    /// Returns the entrant's name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the results of the games played as X.
+    pub fn as_x(&self) -> Record {
+        self.as_x
+    }
+
+    /// Returns the results of the games played as O.
+    pub fn as_o(&self) -> Record {
+        self.as_o
+    }
+
+    /// Returns the results of all games.
+    pub fn total(&self) -> Record {
+        self.as_x + self.as_o
+    }
+
+    /// Returns the points scored: two for a win, and one for a draw.
+    pub fn points(&self) -> u32 {
+        let total = self.total();
+        2 * total.wins + total.draws
+    }
+
+    /// Returns the Elo rating at the end of the tournament.
+    pub fn rating(&self) -> f64 {
+        self.rating
+    }
+}
+
+/// The results of a tournament, best first.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Standings {
+    standings: Vec<Standing>,
+}
+
+impl Standings {
+    /// Returns the standings, best first.
+    pub fn iter(&self) -> std::slice::Iter<'_, Standing> {
+        self.standings.iter()
+    }
+
+    /// Returns the standing of the named entrant.
+    pub fn get(&self, name: &str) -> Option<&Standing> {
+        self.standings.iter().find(|standing| standing.name == name)
+    }
+
+    /// Returns the standings as CSV, with a header line.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "rank,name,x_wins,x_draws,x_losses,o_wins,o_draws,o_losses,points,rating\n",
+        );
+        for (rank, standing) in self.standings.iter().enumerate() {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{},{:.1}\n",
+                rank + 1,
+                csv_field(&standing.name),
+                standing.as_x.wins,
+                standing.as_x.draws,
+                standing.as_x.losses,
+                standing.as_o.wins,
+                standing.as_o.draws,
+                standing.as_o.losses,
+                standing.points(),
+                standing.rating
+            ));
+        }
+        csv
+    }
+
+    /// Returns the standings as a JSON array of objects.
+    pub fn to_json(&self) -> String {
+        let record = |record: Record| {
+            format!(
+                "{{\"wins\": {}, \"draws\": {}, \"losses\": {}}}",
+                record.wins, record.draws, record.losses
+            )
+        };
+        let entries: Vec<String> = self
+            .standings
+            .iter()
+            .enumerate()
+            .map(|(rank, standing)| {
+                format!(
+                    "  {{\"rank\": {}, \"name\": {}, \"as_x\": {}, \"as_o\": {}, \"points\": {}, \"rating\": {:.1}}}",
+                    rank + 1,
+                    json_string(&standing.name),
+                    record(standing.as_x),
+                    record(standing.as_o),
+                    standing.points(),
+                    standing.rating
+                )
+            })
+            .collect();
+        format!("[\n{}\n]\n", entries.join(",\n"))
+    }
+}
+
+impl std::fmt::Display for Standings {
+    /// Writes the standings as a text table, with W/D/L results as X and as O.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let width = self
+            .standings
+            .iter()
+            .map(|standing| standing.name.chars().count())
+            .chain(std::iter::once("Name".len()))
+            .max()
+            .unwrap();
+        writeln!(
+            f,
+            "{:>4}  {:<width$}  {:>11}  {:>11}  {:>6}  {:>6}",
+            "Rank",
+            "Name",
+            "X W/D/L",
+            "O W/D/L",
+            "Points",
+            "Elo",
+            width = width
+        )?;
+        for (rank, standing) in self.standings.iter().enumerate() {
+            writeln!(
+                f,
+                "{:>4}  {:<width$}  {:>11}  {:>11}  {:>6}  {:>6.0}",
+                rank + 1,
+                standing.name,
+                standing.as_x.to_string(),
+                standing.as_o.to_string(),
+                standing.points(),
+                standing.rating,
+                width = width
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        String::from(value)
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut json = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod arena_tests {
+    use super::*;
+    use crate::optimal::OptimalPlayer;
+    use crate::strategies::{Preference, RandomPlayer, WeightedRandomPlayer};
+
+    fn tournament(games_per_match: u32) -> Standings {
+        let mut arena = Arena::new(games_per_match);
+        arena.add("optimal", OptimalPlayer::new);
+        arena.add("random", || RandomPlayer::with_seed(1));
+        arena.add("corners", || {
+            WeightedRandomPlayer::with_seed(Preference::Corners, 2)
+        });
+        arena.run()
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let standings = tournament(10);
+        assert_eq!(standings.iter().count(), 3);
+        for standing in standings.iter() {
+            // Two opponents, ten games each, half of them as X.
+            assert_eq!(standing.as_x().games(), 10);
+            assert_eq!(standing.as_o().games(), 10);
+        }
+
+        let points: u32 = standings.iter().map(|standing| standing.points()).sum();
+        assert_eq!(points, 3 * 10 * 2);
+        let ratings: f64 = standings.iter().map(|standing| standing.rating()).sum();
+        assert!((ratings - 3.0 * INITIAL_RATING).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_optimal_wins_tournament() {
+        let standings = tournament(10);
+        let optimal = standings.iter().next().unwrap();
+        assert_eq!(optimal.name(), "optimal");
+        assert_eq!(optimal.total().losses, 0);
+        assert_gt!(optimal.rating(), INITIAL_RATING);
+        assert_eq!(standings.get("optimal"), Some(optimal));
+        assert_eq!(standings.get("nobody"), None);
+    }
+
+    #[test]
+    fn test_tournaments_repeat() {
+        assert_eq!(tournament(6), tournament(6));
+    }
+
+    #[test]
+    #[should_panic(expected = "Duplicate entrant: random")]
+    fn test_duplicate_name() {
+        let mut arena = Arena::new(1);
+        arena.add("random", RandomPlayer::new);
+        arena.add("random", RandomPlayer::new);
+    }
+
+    fn standings(entries: &[(&str, Record, Record, f64)]) -> Standings {
+        Standings {
+            standings: entries
+                .iter()
+                .map(|(name, as_x, as_o, rating)| Standing {
+                    name: String::from(*name),
+                    as_x: *as_x,
+                    as_o: *as_o,
+                    rating: *rating,
+                })
+                .collect(),
+        }
+    }
+
+    fn record(wins: u32, draws: u32, losses: u32) -> Record {
+        Record {
+            wins,
+            draws,
+            losses,
+        }
+    }
+
+    #[test]
+    fn test_text_table() {
+        let standings = standings(&[
+            ("optimal", record(2, 0, 0), record(1, 1, 0), 1516.3),
+            ("random", record(0, 1, 1), record(0, 0, 2), 1483.7),
+        ]);
+        assert_eq!(
+            standings.to_string(),
+            "Rank  Name         X W/D/L      O W/D/L  Points     Elo\n\
+            \x20  1  optimal        2/0/0        1/1/0       7    1516\n\
+            \x20  2  random         0/1/1        0/0/2       1    1484\n"
+        );
+    }
+
+    #[test]
+    fn test_csv() {
+        let standings = standings(&[("a, \"b\"", record(1, 2, 3), record(4, 5, 6), 1500.0)]);
+        assert_eq!(
+            standings.to_csv(),
+            "rank,name,x_wins,x_draws,x_losses,o_wins,o_draws,o_losses,points,rating\n\
+             1,\"a, \"\"b\"\"\",1,2,3,4,5,6,17,1500.0\n"
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let standings = standings(&[("a \"b\"", record(1, 2, 3), record(4, 5, 6), 1500.0)]);
+        assert_eq!(
+            standings.to_json(),
+            "[\n  {\"rank\": 1, \"name\": \"a \\\"b\\\"\", \
+             \"as_x\": {\"wins\": 1, \"draws\": 2, \"losses\": 3}, \
+             \"as_o\": {\"wins\": 4, \"draws\": 5, \"losses\": 6}, \
+             \"points\": 17, \"rating\": 1500.0}\n]\n"
+        );
+    }
+} // mod arena_tests
@@ -1,6 +1,6 @@
 //! Utilities for manipulating and querying a tic-tac-toe game board.
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 /// Represents the player of the game (X or O).
 pub enum Token {
     X,
@@ -8,17 +8,30 @@ pub enum Token {
 }
 
 impl Token {
-    #[cfg(test)]
     #[doc(hidden)]
-    fn from_char(value: char) -> Option<Token> {
-        if value == 'X' {
-            Some(Token::X)
-        } else if value == 'O' {
-            Some(Token::O)
-        } else if value == '-' {
-            None
-        } else {
-            panic!("Invalid character for player: '{}'", value);
+    fn from_char(value: char) -> Result<Option<Token>, ParseBoardError> {
+        match value {
+            'X' => Ok(Some(Token::X)),
+            'O' => Ok(Some(Token::O)),
+            '-' => Ok(None),
+            _ => Err(ParseBoardError::InvalidCharacter(value)),
+        }
+    }
+
+    /// Returns the other player.
+    pub fn opponent(self) -> Token {
+        match self {
+            Token::X => Token::O,
+            Token::O => Token::X,
+        }
+    }
+
+    #[doc(hidden)]
+    fn to_char(token: Option<Token>) -> char {
+        match token {
+            Some(Token::X) => 'X',
+            Some(Token::O) => 'O',
+            None => '-',
         }
     }
 }
@@ -32,12 +45,15 @@ impl std::fmt::Display for Token {
     }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+/// The largest number of rows or columns a board may have.
+pub const MAX_SIZE: u8 = 16;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 /// A position in a tic-tac-toe game board.
 pub struct Position {
-    /// row number (0 = top, 2 = bottom)
+    /// row number (0 = top)
     row: u8,
-    /// column number (0 = left, 2 = right)
+    /// column number (0 = left)
     column: u8,
 }
 
@@ -46,29 +62,220 @@ impl Position {
     ///
     /// # Panics
     ///
-    /// Panics if given row or column is out of range.
+    /// Panics if given row or column is not less than MAX_SIZE.
     pub fn new(row: u8, column: u8) -> Position {
-        if row > 2 {
-            panic!("Invalid row: {}", row);
+        Position::try_new(row, column).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Creates a position given the row and column,
+    /// or returns MoveError::OutOfRange if either is not less than MAX_SIZE.
+    ///
+    /// Examples
+    /// ```
+    /// use tictactoelib::board::{MoveError, Position, MAX_SIZE};
+    /// assert!(Position::try_new(2, 2).is_ok());
+    /// assert_eq!(
+    ///     Position::try_new(MAX_SIZE, 0),
+    ///     Err(MoveError::OutOfRange { row: MAX_SIZE, column: 0 })
+    /// );
+    /// ```
+    pub fn try_new(row: u8, column: u8) -> Result<Position, MoveError> {
+        if row >= MAX_SIZE || column >= MAX_SIZE {
+            return Err(MoveError::OutOfRange { row, column });
         }
-        if column > 2 {
-            panic!("Invalid column: {}", column);
+        Ok(Position { row, column })
+    }
+
+    /// Returns the row number (0 = top)
+    pub fn row(&self) -> u8 {
+        self.row
+    }
+
+    /// Returns the column number (0 = left)
+    pub fn column(&self) -> u8 {
+        self.column
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+/// The directions a line can run in.
+pub enum Direction {
+    /// Along a row, from left to right.
+    Across,
+    /// Down a column.
+    Down,
+    /// Down and to the right, like '\'.
+    Diagonal,
+    /// Down and to the left, like '/'.
+    AntiDiagonal,
+}
+
+impl Direction {
+    /// Every direction.
+    pub const ALL: [Direction; 4] = [
+        Direction::Across,
+        Direction::Down,
+        Direction::Diagonal,
+        Direction::AntiDiagonal,
+    ];
+
+    /// Returns the change in row and column from one position of a line to the next.
+    pub fn step(self) -> (i8, i8) {
+        match self {
+            Direction::Across => (0, 1),
+            Direction::Down => (1, 0),
+            Direction::Diagonal => (1, 1),
+            Direction::AntiDiagonal => (1, -1),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+/// A line of positions that wins the game when one player occupies all of them.
+///
+/// Lines are as long as the board's run length, so a large board has overlapping lines
+/// along each row, column and diagonal.
+pub struct Line {
+    #[doc(hidden)]
+    start: Position,
+    #[doc(hidden)]
+    direction: Direction,
+    #[doc(hidden)]
+    length: u8,
+}
+
+impl Line {
+    /// Returns the first position of the line: its leftmost, or for a column its top.
+    pub fn start(&self) -> Position {
+        self.start
+    }
+
+    /// Returns the last position of the line.
+    pub fn end(&self) -> Position {
+        self.position(self.length - 1)
+    }
+
+    /// Returns the direction the line runs in, from its start to its end.
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Returns the number of positions in the line.
+    pub fn len(&self) -> u8 {
+        self.length
+    }
+
+    /// Returns false, as every line has at least one position.
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Returns the positions of the line, from its start to its end.
+    pub fn positions(&self) -> impl Iterator<Item = Position> + '_ {
+        (0..self.length).map(move |step| self.position(step))
+    }
+
+    /// Indicates whether the position is one of the line's positions.
+    pub fn contains(&self, position: Position) -> bool {
+        self.positions().any(|on_line| on_line == position)
+    }
+
+    #[doc(hidden)]
+    fn position(&self, step: u8) -> Position {
+        let (row_step, column_step) = self.direction.step();
+        Position {
+            row: (self.start.row as i16 + row_step as i16 * step as i16) as u8,
+            column: (self.start.column as i16 + column_step as i16 * step as i16) as u8,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+/// The reasons a move can be rejected by the board.
+pub enum MoveError {
+    /// The position is already occupied.
+    Occupied(Position),
+    /// The given Token tried to move when it is not their turn.
+    OutOfTurn(Token),
+    /// The game has already been won or drawn.
+    GameOver,
+    /// The given row and column do not lie on the board.
+    OutOfRange { row: u8, column: u8 },
+}
+
+impl std::fmt::Display for MoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            MoveError::Occupied(at) => {
+                write!(
+                    f,
+                    "Position [{},{}] is already occupied!",
+                    at.row, at.column
+                )
+            }
+            MoveError::OutOfTurn(player) => write!(f, "It is not {}'s turn!", player),
+            MoveError::GameOver => write!(f, "The game is already over!"),
+            MoveError::OutOfRange { row, column } => {
+                write!(f, "Position [{},{}] is out of range!", row, column)
+            }
         }
-        Position { row, column }
     }
 }
 
-#[derive(PartialEq, Debug, Copy, Clone)]
+impl std::error::Error for MoveError {}
+
+/// How a game is scored when a player completes a line.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Rules {
+    /// The player who completes a line wins.
+    #[default]
+    Standard,
+    /// Misère, or reverse, tic-tac-toe: the player who completes a line loses.
+    Misere,
+}
+
+/// The largest number of positions a board may have.
+const MAX_CELLS: usize = MAX_SIZE as usize * MAX_SIZE as usize;
+
+#[derive(Copy, Clone)]
 /// Represents a tic-tac-toe game board.
+///
+/// The standard board is 3x3, and is won with three in a row,
+/// but any board up to MAX_SIZE x MAX_SIZE, with any run length, can be created.
+///
+/// The board remembers the order moves were made in, so they can be undone and redone.
+/// Boards are equal when their positions are the same, however they were reached.
 pub struct Board {
+    /// The positions occupied by X, as a bitboard: one mask per row, with bit n for column n.
+    #[doc(hidden)]
+    x_rows: [u16; MAX_SIZE as usize],
+    /// The positions occupied by O, in the same form as x_rows.
+    #[doc(hidden)]
+    o_rows: [u16; MAX_SIZE as usize],
     #[doc(hidden)]
-    positions: [[Option<Token>; 3]; 3],
+    rows: u8,
     #[doc(hidden)]
-    turn_number: u8,
+    columns: u8,
+    #[doc(hidden)]
+    run_length: u8,
+    #[doc(hidden)]
+    rules: Rules,
+    #[doc(hidden)]
+    turn_number: u16,
+    /// The recorded moves, as indexes of (row * columns + column),
+    /// including any undone moves that can still be redone.
+    #[doc(hidden)]
+    history: [u8; MAX_CELLS],
+    /// How many of the recorded moves are currently on the board.
+    #[doc(hidden)]
+    undo_count: u16,
+    /// How many undone moves follow them in the history.
+    #[doc(hidden)]
+    redo_count: u16,
 }
 
 impl Board {
-    /// Creates an empty tic-tac-toe game board.
+    /// Creates an empty, standard 3x3 tic-tac-toe game board.
     ///
     /// Examples
     /// ```
@@ -78,83 +285,329 @@ impl Board {
     /// ```
     #[allow(clippy::new_without_default)]
     pub fn new() -> Board {
+        Board::with_size(3, 3, 3)
+    }
+
+    /// Creates an empty 3x3 board, played by misère rules: three in a row loses.
+    ///
+    /// Examples
+    /// ```
+    /// use tictactoelib::board::{Board, GameResult, Position, Rules, Token};
+    /// let mut board = Board::misere();
+    /// assert_eq!(board.rules(), Rules::Misere);
+    /// for column in 0..2 {
+    ///     board.add_move(Token::X, Position::new(0, column));
+    ///     board.add_move(Token::O, Position::new(1, column));
+    /// }
+    /// board.add_move(Token::X, Position::new(0, 2));
+    /// assert_eq!(board.get_game_result(), GameResult::Win(Token::O));
+    /// ```
+    pub fn misere() -> Board {
+        let mut board = Board::new();
+        board.set_rules(Rules::Misere);
+        board
+    }
+
+    /// Creates an empty game board with the given number of rows and columns,
+    /// which is won by getting run_length tokens in a row.
+    ///
+    /// Examples
+    /// ```
+    /// use tictactoelib::board::{Board, GameResult};
+    /// let gomoku = Board::with_size(15, 15, 5);
+    /// assert_eq!(gomoku.empty_positions().len(), 225);
+    /// assert_eq!(gomoku.get_game_result(), GameResult::InProgress);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if rows or columns is zero or greater than MAX_SIZE.
+    ///
+    /// Panics if run_length is zero, or too long to fit on the board.
+    pub fn with_size(rows: u8, columns: u8, run_length: u8) -> Board {
+        if rows == 0 || rows > MAX_SIZE || columns == 0 || columns > MAX_SIZE {
+            panic!("Invalid board size: {}x{}", rows, columns);
+        }
+        if run_length == 0 || run_length > std::cmp::max(rows, columns) {
+            panic!("Invalid run length: {}", run_length);
+        }
         Board {
-            positions: [[None, None, None], [None, None, None], [None, None, None]],
-            turn_number: 1,
-        } // Starts at 1, not 0!
+            x_rows: [0; MAX_SIZE as usize],
+            o_rows: [0; MAX_SIZE as usize],
+            rows,
+            columns,
+            run_length,
+            rules: Rules::Standard,
+            turn_number: 1, // Starts at 1, not 0!
+            history: [0; MAX_CELLS],
+            undo_count: 0,
+            redo_count: 0,
+        }
     }
 
     #[cfg(test)]
     pub fn from_string(contents: &str) -> Board {
-        if contents.len() != 9 {
-            panic!(
-                "Invalid string length {} for board: '{}'",
-                contents.len(),
-                contents
-            );
+        Board::from_compact_str(contents).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Parses a board in compact form, as produced by to_compact_string().
+    ///
+    /// Each position is one of 'X', 'O' or '-' (empty), listed row by row.
+    /// Square boards may be written as a single run of characters;
+    /// otherwise rows are separated by '/'.
+    /// The board is won by filling a row, column or diagonal,
+    /// i.e. the run length is the smaller of the number of rows and columns.
+    ///
+    /// Examples
+    /// ```
+    /// use tictactoelib::board::{Board, GameResult, Token};
+    /// let board = Board::from_compact_str("XO-OO-XXX").unwrap();
+    /// assert_eq!(board.get_game_result(), GameResult::Win(Token::X));
+    /// assert_eq!(board.to_compact_string(), "XO-OO-XXX");
+    /// assert!(Board::from_compact_str("XXXOOO---").is_err());
+    /// ```
+    pub fn from_compact_str(contents: &str) -> Result<Board, ParseBoardError> {
+        let rows: Vec<&str> = contents.split('/').collect();
+        Board::from_rows(&rows, None)
+    }
+
+    /// Parses a board like FromStr, but with the given run length needed to win.
+    ///
+    /// Examples
+    /// ```
+    /// use tictactoelib::board::Board;
+    /// let board = Board::parse_with_run_length("X--- -O-- ---- ----", 3).unwrap();
+    /// assert_eq!((board.rows(), board.columns(), board.run_length()), (4, 4, 3));
+    /// ```
+    pub fn parse_with_run_length(contents: &str, run_length: u8) -> Result<Board, ParseBoardError> {
+        let rows: Vec<&str> = contents
+            .split(|the_char: char| the_char.is_whitespace() || the_char == '/')
+            .filter(|row| !row.is_empty())
+            .collect();
+        Board::from_rows(&rows, Some(run_length))
+    }
+
+    /// Returns the board in compact form, which can be read back by from_compact_str() or parse().
+    ///
+    /// Square boards are written as a single run of characters,
+    /// and other boards have their rows separated by '/'.
+    pub fn to_compact_string(&self) -> String {
+        let mut compact = String::with_capacity(self.cell_count() + self.rows as usize);
+        for row in 0..self.rows {
+            if row > 0 && self.rows != self.columns {
+                compact.push('/');
+            }
+            for column in 0..self.columns {
+                compact.push(Token::to_char(self.get(Position { row, column })));
+            }
         }
-        let x_count: i8 = contents
-            .chars()
-            .filter(|the_char| *the_char == 'X')
-            .collect::<Vec<char>>()
-            .len() as i8;
-        let o_count: i8 = contents
-            .chars()
-            .filter(|the_char| *the_char == 'O')
-            .collect::<Vec<char>>()
-            .len() as i8;
-        let diff_count = x_count - o_count;
-        if diff_count != 0 && diff_count != 1 {
-            panic!("Invalid number of Xs and Os!");
-        }
-
-        let blank_count = contents
-            .chars()
-            .filter(|the_char| *the_char == '-')
-            .collect::<Vec<char>>()
-            .len();
-        let mut chars = contents.chars();
-        let board = Board {
-            positions: [
-                [
-                    Token::from_char(chars.next().unwrap()),
-                    Token::from_char(chars.next().unwrap()),
-                    Token::from_char(chars.next().unwrap()),
-                ],
-                [
-                    Token::from_char(chars.next().unwrap()),
-                    Token::from_char(chars.next().unwrap()),
-                    Token::from_char(chars.next().unwrap()),
-                ],
-                [
-                    Token::from_char(chars.next().unwrap()),
-                    Token::from_char(chars.next().unwrap()),
-                    Token::from_char(chars.next().unwrap()),
-                ],
-            ],
-            turn_number: 9 - blank_count as u8 + 1,
+        compact
+    }
+
+    /// Builds a board from its rows, and checks that it can be reached by legal play.
+    #[doc(hidden)]
+    fn from_rows(rows: &[&str], run_length: Option<u8>) -> Result<Board, ParseBoardError> {
+        if rows.is_empty() {
+            return Err(ParseBoardError::InvalidLength(0));
+        }
+        let rows: Vec<Vec<char>> = rows.iter().map(|row| row.chars().collect()).collect();
+        let rows: Vec<Vec<char>> = if rows.len() == 1 {
+            // A single run of characters is a square board.
+            let cells = &rows[0];
+            let size = (1..=MAX_SIZE as usize)
+                .find(|size| size * size == cells.len())
+                .ok_or(ParseBoardError::InvalidLength(cells.len()))?;
+            cells.chunks(size).map(|row| row.to_vec()).collect()
+        } else {
+            rows
         };
-        #[cfg(debug_assertions)]
-        board.check_invariants();
-        board
+
+        let row_count = rows.len();
+        let column_count = rows[0].len();
+        if let Some(row) = rows.iter().find(|row| row.len() != column_count) {
+            return Err(ParseBoardError::RaggedRows {
+                expected: column_count,
+                found: row.len(),
+            });
+        }
+        if row_count == 0
+            || column_count == 0
+            || row_count > MAX_SIZE as usize
+            || column_count > MAX_SIZE as usize
+        {
+            return Err(ParseBoardError::InvalidSize {
+                rows: row_count,
+                columns: column_count,
+            });
+        }
+        let (rows_u8, columns_u8) = (row_count as u8, column_count as u8);
+        let run_length = run_length.unwrap_or_else(|| std::cmp::min(rows_u8, columns_u8));
+        if run_length == 0 || run_length > std::cmp::max(rows_u8, columns_u8) {
+            return Err(ParseBoardError::InvalidRunLength(run_length));
+        }
+
+        let mut board = Board::with_size(rows_u8, columns_u8, run_length);
+        let mut x_count = 0;
+        let mut o_count = 0;
+        for (row, cells) in rows.iter().enumerate() {
+            for (column, cell) in cells.iter().enumerate() {
+                let token = Token::from_char(*cell)?;
+                match token {
+                    Some(Token::X) => x_count += 1,
+                    Some(Token::O) => o_count += 1,
+                    None => {}
+                }
+                board.set(
+                    Position {
+                        row: row as u8,
+                        column: column as u8,
+                    },
+                    token,
+                );
+            }
+        }
+        if x_count != o_count && x_count != o_count + 1 {
+            return Err(ParseBoardError::InvalidTokenCount { x_count, o_count });
+        }
+        board.turn_number = (x_count + o_count) as u16 + 1;
+
+        board.check_reachable()?;
+        Ok(board)
     }
 
-    /// Returns all of the empty positions in a vector
-    pub fn empty_positions(&self) -> Vec<Position> {
-        let mut vec = Vec::with_capacity(9);
-        for row in 0..3 {
-            for column in 0..3 {
-                if self.positions[row][column] == None {
-                    vec.push(Position::new(row as u8, column as u8));
+    /// Checks that the board can be reached by legal play from an empty board.
+    #[doc(hidden)]
+    fn check_reachable(&self) -> Result<(), ParseBoardError> {
+        let x_won = self.has_winning_line(Token::X);
+        let o_won = self.has_winning_line(Token::O);
+        let winner = match (x_won, o_won) {
+            (true, true) => return Err(ParseBoardError::MultipleWinners),
+            (true, false) => Token::X,
+            (false, true) => Token::O,
+            (false, false) => return Ok(()),
+        };
+
+        // The winner must have made the last move...
+        let last_to_move = if self.turn_number % 2 == 1 {
+            Token::O
+        } else {
+            Token::X
+        };
+        if winner != last_to_move {
+            return Err(ParseBoardError::PlayedAfterWin(winner));
+        }
+
+        // ...and that move must have completed every one of their winning lines.
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let position = Position { row, column };
+                if self.get(position) == Some(winner) {
+                    let mut before = *self;
+                    before.set(position, None);
+                    if !before.has_winning_line(winner) {
+                        return Ok(());
+                    }
                 }
             }
         }
-        vec
+        Err(ParseBoardError::PlayedAfterWin(winner))
+    }
+
+    /// Returns how a completed line is scored.
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    /// Changes how a completed line is scored, for this board and every board made from it.
+    pub fn set_rules(&mut self, rules: Rules) {
+        self.rules = rules;
+    }
+
+    /// Returns the number of rows on the board.
+    pub fn rows(&self) -> u8 {
+        self.rows
+    }
+
+    /// Returns the number of columns on the board.
+    pub fn columns(&self) -> u8 {
+        self.columns
+    }
+
+    /// Returns how many tokens in a row are needed to win.
+    pub fn run_length(&self) -> u8 {
+        self.run_length
+    }
+
+    /// Creates a position given the row and column,
+    /// or returns MoveError::OutOfRange if it does not lie on this board.
+    pub fn try_position(&self, row: u8, column: u8) -> Result<Position, MoveError> {
+        let position = Position::try_new(row, column)?;
+        if self.contains(position) {
+            Ok(position)
+        } else {
+            Err(MoveError::OutOfRange { row, column })
+        }
+    }
+
+    /// Indicates whether or not the given position lies on the board
+    pub fn contains(&self, position: Position) -> bool {
+        position.row < self.rows && position.column < self.columns
+    }
+
+    /// Returns all of the empty positions in a vector
+    pub fn empty_positions(&self) -> Vec<Position> {
+        self.empty_positions_iter().collect()
+    }
+
+    /// Returns an iterator over the empty positions, row by row,
+    /// without allocating.
+    ///
+    /// Examples
+    /// ```
+    /// use tictactoelib::board::{Board, Position, Token};
+    /// let mut board = Board::new();
+    /// board.add_move(Token::X, Position::new(0, 0));
+    /// assert_eq!(board.empty_positions_iter().next(), Some(Position::new(0, 1)));
+    /// assert_eq!(board.empty_positions_iter().count(), 8);
+    /// ```
+    pub fn empty_positions_iter(&self) -> impl Iterator<Item = Position> + '_ {
+        let full_row = (1u32 << self.columns) - 1;
+        (0..self.rows).flat_map(move |row| {
+            let empty =
+                !u32::from(self.x_rows[row as usize] | self.o_rows[row as usize]) & full_row;
+            (0..self.columns)
+                .filter(move |column| empty & (1 << column) != 0)
+                .map(move |column| Position { row, column })
+        })
     }
 
     /// Indicates whether or not the indicated position is empty
+    ///
+    /// Positions that are not on the board are never empty.
     pub fn is_position_unused(&self, position: Position) -> bool {
-        self.positions[position.row as usize][position.column as usize] == None
+        self.contains(position) && self.get(position).is_none()
+    }
+
+    /// Returns the token at the indicated position, or None if it is empty.
+    ///
+    /// Positions that are not on the board are always empty.
+    ///
+    /// # Examples
+    /// ```
+    /// use tictactoelib::board::{Board, Position, Token};
+    ///
+    /// let board: Board = "X--/-O-/---".parse().unwrap();
+    /// assert_eq!(board.token_at(Position::new(0, 0)), Some(Token::X));
+    /// assert_eq!(board.token_at(Position::new(1, 1)), Some(Token::O));
+    /// assert_eq!(board.token_at(Position::new(2, 2)), None);
+    /// assert_eq!(board.token_at(Position::new(5, 5)), None);
+    /// ```
+    pub fn token_at(&self, position: Position) -> Option<Token> {
+        if self.contains(position) {
+            self.get(position)
+        } else {
+            None
+        }
     }
 
     /// Returns whose turn is next, or None if the game is over.
@@ -174,72 +627,167 @@ impl Board {
     ///
     /// # Panics
     ///
+    /// Panics if the position is not on the board.
+    ///
     /// Panics if the position is already occupied.
     ///
     /// Panics if the given player is playing out of turn.
     /// Token::X goes first, followed by Token::O, ...
+    ///
+    /// Panics if the game is already over.
     pub fn add_move(&mut self, player: Token, at: Position) {
+        self.try_add_move(player, at)
+            .unwrap_or_else(|error| panic!("{}", error));
+    }
+
+    /// Marks the given position as occupied by the given player,
+    /// or returns a MoveError, leaving the board unchanged, if the move is not allowed.
+    ///
+    /// Examples
+    /// ```
+    /// use tictactoelib::board::{Board, MoveError, Position, Token};
+    /// let mut board = Board::new();
+    /// let center = Position::new(1, 1);
+    /// assert_eq!(board.try_add_move(Token::X, center), Ok(()));
+    /// assert_eq!(board.try_add_move(Token::X, center), Err(MoveError::OutOfTurn(Token::X)));
+    /// assert_eq!(board.try_add_move(Token::O, center), Err(MoveError::Occupied(center)));
+    /// ```
+    pub fn try_add_move(&mut self, player: Token, at: Position) -> Result<(), MoveError> {
+        self.try_move(player, at, true)
+    }
+
+    /// Marks the position like try_add_move(), but lets either player move,
+    /// for boards that are part of a larger game, where a player may move twice in a row.
+    pub(crate) fn try_place(&mut self, player: Token, at: Position) -> Result<(), MoveError> {
+        self.try_move(player, at, false)
+    }
+
+    #[doc(hidden)]
+    fn try_move(&mut self, player: Token, at: Position, check_turn: bool) -> Result<(), MoveError> {
         #[cfg(debug_assertions)]
         self.check_invariants();
 
-        if player != self.whose_turn().unwrap() {
-            panic!("It is not {}'s turn!", player);
+        if !self.contains(at) {
+            return Err(MoveError::OutOfRange {
+                row: at.row,
+                column: at.column,
+            });
+        }
+        match self.whose_turn() {
+            None => return Err(MoveError::GameOver),
+            Some(whose_turn) if check_turn && whose_turn != player => {
+                return Err(MoveError::OutOfTurn(player))
+            }
+            Some(_) => {}
         }
-        if let Some(_player) = self.positions[at.row as usize][at.column as usize] {
-            panic!("Position [{},{}] is already occupied!", at.row, at.column);
+        if let Some(_player) = self.get(at) {
+            return Err(MoveError::Occupied(at));
         }
-        self.positions[at.row as usize][at.column as usize] = Some(player);
+        self.set(at, Some(player));
         self.turn_number += 1;
+        self.history[self.undo_count as usize] = self.index_of(at);
+        self.undo_count += 1;
+        self.redo_count = 0;
 
         #[cfg(debug_assertions)]
         self.check_invariants();
+        Ok(())
     }
 
-    /// Gets the result of the current game.
-    pub fn get_game_result(&self) -> GameResult {
-        let mut result = None;
-        for row in 0..3 {
-            if let Some(player) = self.positions[row][0] {
-                if self.positions[row][1] == Some(player) && self.positions[row][2] == Some(player)
-                {
-                    if result != None && result != Some(GameResult::Win(player)) {
-                        panic!("Game cannot have multiple winners!");
-                    }
-                    result = Some(GameResult::Win(player));
-                }
-            }
+    /// Takes back the most recent move, returning its position,
+    /// or None if there are no recorded moves to undo.
+    ///
+    /// Boards created by parsing a string have no recorded moves,
+    /// so only moves made after parsing can be undone.
+    ///
+    /// Examples
+    /// ```
+    /// use tictactoelib::board::{Board, Position, Token};
+    /// let mut board = Board::new();
+    /// board.add_move(Token::X, Position::new(1, 1));
+    /// assert_eq!(board.undo_move(), Some(Position::new(1, 1)));
+    /// assert_eq!(board, Board::new());
+    /// assert_eq!(board.redo_move(), Some(Position::new(1, 1)));
+    /// assert_eq!(board.whose_turn(), Some(Token::O));
+    /// ```
+    pub fn undo_move(&mut self) -> Option<Position> {
+        if self.undo_count == 0 {
+            return None;
         }
+        self.undo_count -= 1;
+        self.redo_count += 1;
+        let at = self.position_of(self.history[self.undo_count as usize]);
+        self.set(at, None);
+        self.turn_number -= 1;
 
-        for column in 0..3 {
-            if let Some(player) = self.positions[0][column] {
-                if self.positions[1][column] == Some(player)
-                    && self.positions[2][column] == Some(player)
-                {
-                    if result != None && result != Some(GameResult::Win(player)) {
-                        panic!("Game cannot have multiple winners!");
-                    }
-                    result = Some(GameResult::Win(player));
-                }
-            }
-        }
+        #[cfg(debug_assertions)]
+        self.check_invariants();
+        Some(at)
+    }
 
-        // diagonal in direction '\'
-        if let Some(player) = self.positions[0][0] {
-            if self.positions[1][1] == Some(player) && self.positions[2][2] == Some(player) {
-                result = Some(GameResult::Win(player));
-            }
+    /// Replays the most recently undone move, returning its position,
+    /// or None if there is nothing to redo.
+    ///
+    /// Making a new move with add_move() discards any moves that could have been redone.
+    pub fn redo_move(&mut self) -> Option<Position> {
+        if self.redo_count == 0 {
+            return None;
         }
+        let at = self.position_of(self.history[self.undo_count as usize]);
+        let player = self.whose_turn().unwrap();
+        self.set(at, Some(player));
+        self.turn_number += 1;
+        self.undo_count += 1;
+        self.redo_count -= 1;
 
-        // diagonal in direction '/'
-        if let Some(player) = self.positions[2][0] {
-            if self.positions[1][1] == Some(player) && self.positions[0][2] == Some(player) {
-                result = Some(GameResult::Win(player));
-            }
+        #[cfg(debug_assertions)]
+        self.check_invariants();
+        Some(at)
+    }
+
+    /// Returns the position of the most recent recorded move, if any.
+    pub fn last_move(&self) -> Option<Position> {
+        if self.undo_count == 0 {
+            None
+        } else {
+            Some(self.position_of(self.history[self.undo_count as usize - 1]))
         }
+    }
+
+    /// Returns the recorded moves currently on the board, in the order they were made.
+    ///
+    /// X made the first move in the list unless the board was parsed from a string
+    /// part way through a game.
+    pub fn moves(&self) -> Vec<Position> {
+        self.history[..self.undo_count as usize]
+            .iter()
+            .map(|&index| self.position_of(index))
+            .collect()
+    }
+
+    /// Gets the result of the current game.
+    ///
+    /// The game is over once either player completes a line.
+    /// Under standard rules that player wins, and under misère rules their opponent does.
+    ///
+    /// # Panics
+    ///
+    /// Panics if both players have a winning line,
+    /// which cannot happen on a board built with try_add_move() or add_move().
+    pub fn get_game_result(&self) -> GameResult {
+        let line_owner = match (self.has_run(&self.x_rows), self.has_run(&self.o_rows)) {
+            (true, true) => panic!("Game cannot have multiple winners!"),
+            (true, false) => Some(Token::X),
+            (false, true) => Some(Token::O),
+            (false, false) => None,
+        };
 
-        if let Some(result) = result {
-            result
-        } else if self.turn_number <= 9 {
+        if let Some(line_owner) = line_owner {
+            match self.rules {
+                Rules::Standard => GameResult::Win(line_owner),
+                Rules::Misere => GameResult::Win(line_owner.opponent()),
+            }
+        } else if (self.turn_number as usize) <= self.cell_count() {
             GameResult::InProgress
         } else {
             GameResult::Draw
@@ -247,75 +795,619 @@ impl Board {
     }
 
     #[doc(hidden)]
-    #[cfg(debug_assertions)]
-    fn check_invariants(&self) {
-        let _winner = self.get_game_result();
-        if self.turn_number == 0 || self.turn_number > 10 {
-            panic!("Invalid turn number {}!", self.turn_number);
+    fn get(&self, position: Position) -> Option<Token> {
+        let bit = 1 << position.column;
+        if self.x_rows[position.row as usize] & bit != 0 {
+            Some(Token::X)
+        } else if self.o_rows[position.row as usize] & bit != 0 {
+            Some(Token::O)
+        } else {
+            None
         }
     }
-} // impl Board
 
-impl std::fmt::Display for Board {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        // Will look something like:
-        // ┌───┐
-        // |X O|
-        // | X |
-        // |OOX|
+    #[doc(hidden)]
+    fn set(&mut self, position: Position, token: Option<Token>) {
+        let bit = 1 << position.column;
+        let row = position.row as usize;
+        self.x_rows[row] &= !bit;
+        self.o_rows[row] &= !bit;
+        match token {
+            Some(Token::X) => self.x_rows[row] |= bit,
+            Some(Token::O) => self.o_rows[row] |= bit,
+            None => {}
+        }
+    }
+
+    #[doc(hidden)]
+    fn index_of(&self, position: Position) -> u8 {
+        (position.row as usize * self.columns as usize + position.column as usize) as u8
+    }
+
+    #[doc(hidden)]
+    fn position_of(&self, index: u8) -> Position {
+        Position {
+            row: index / self.columns,
+            column: index % self.columns,
+        }
+    }
+
+    #[doc(hidden)]
+    fn cell_count(&self) -> usize {
+        self.rows as usize * self.columns as usize
+    }
+
+    /// Indicates whether or not the given player occupies an entire line.
+    #[doc(hidden)]
+    fn has_winning_line(&self, player: Token) -> bool {
+        match player {
+            Token::X => self.has_run(&self.x_rows),
+            Token::O => self.has_run(&self.o_rows),
+        }
+    }
+
+    /// Indicates whether or not a player's bitboard has run_length positions in a line.
+    ///
+    /// For each Direction, the mask of each row is ANDed with the masks of the rows the
+    /// line steps through, shifted so that the positions of a line all land on the bit
+    /// of its starting column.
+    #[doc(hidden)]
+    fn has_run(&self, masks: &[u16; MAX_SIZE as usize]) -> bool {
+        // Written out, rather than looping over Direction::ALL, so that each step is a constant.
+        self.has_run_in(masks, Direction::Across)
+            || self.has_run_in(masks, Direction::Down)
+            || self.has_run_in(masks, Direction::Diagonal)
+            || self.has_run_in(masks, Direction::AntiDiagonal)
+    }
+
+    #[doc(hidden)]
+    #[inline(always)]
+    fn has_run_in(&self, masks: &[u16; MAX_SIZE as usize], direction: Direction) -> bool {
+        let rows = self.rows as usize;
+        let run_length = self.run_length as usize;
+        let (row_step, column_step) = direction.step();
+        let row_step = row_step as usize;
+        let row_span = (run_length - 1) * row_step;
+        for row in 0..rows.saturating_sub(row_span) {
+            let mut run = u32::from(masks[row]);
+            for step in 1..run_length {
+                let next = u32::from(masks[row + step * row_step]);
+                run &= match column_step {
+                    1 => next >> step,
+                    -1 => next << step,
+                    _ => next,
+                };
+            }
+            if run != 0 {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Returns every line on the board: each run of run_length positions
+    /// across, down or diagonally, that wins the game for a player who fills it.
+    ///
+    /// These are the lines get_game_result() looks for.
+    ///
+    /// # Examples
+    /// ```
+    /// use tictactoelib::board::Board;
+    /// assert_eq!(Board::new().lines().count(), 8);
+    /// assert_eq!(Board::with_size(4, 4, 3).lines().count(), 24);
+    /// ```
+    pub fn lines(&self) -> impl Iterator<Item = Line> + '_ {
+        let span = self.run_length as i16 - 1;
+        Direction::ALL.iter().flat_map(move |&direction| {
+            let (row_step, column_step) = direction.step();
+            (0..self.rows).flat_map(move |row| {
+                (0..self.columns).filter_map(move |column| {
+                    let end_row = row as i16 + span * row_step as i16;
+                    let end_column = column as i16 + span * column_step as i16;
+                    let fits = end_row < self.rows as i16
+                        && end_column >= 0
+                        && end_column < self.columns as i16;
+                    if fits {
+                        Some(Line {
+                            start: Position { row, column },
+                            direction,
+                            length: self.run_length,
+                        })
+                    } else {
+                        None
+                    }
+                })
+            })
+        })
+    }
+
+    /// Returns the lines that the token could still fill: those without any of the opponent's tokens.
+    ///
+    /// # Examples
+    /// ```
+    /// use tictactoelib::board::{Board, Token};
+    /// let board: Board = "---/-X-/---".parse().unwrap();
+    /// assert_eq!(board.open_lines(Token::X).len(), 8);
+    /// assert_eq!(board.open_lines(Token::O).len(), 4);
+    /// ```
+    pub fn open_lines(&self, token: Token) -> Vec<Line> {
+        let opponent = token.opponent();
+        self.lines()
+            .filter(|line| self.count_on_line(line, opponent) == 0)
+            .collect()
+    }
+
+    /// Returns the empty positions where the token would complete a line, in board order.
+    ///
+    /// # Examples
+    /// ```
+    /// use tictactoelib::board::{Board, Position, Token};
+    /// let board: Board = "XX-/OO-/---".parse().unwrap();
+    /// assert_eq!(board.winning_moves(Token::X), vec![Position::new(0, 2)]);
+    /// assert_eq!(board.winning_moves(Token::O), vec![Position::new(1, 2)]);
+    /// ```
+    pub fn winning_moves(&self, token: Token) -> Vec<Position> {
+        let mut moves: Vec<Position> = self
+            .open_lines(token)
+            .iter()
+            .filter(|line| self.count_on_line(line, token) + 1 == line.len())
+            .filter_map(|line| {
+                line.positions()
+                    .find(|position| self.get(*position).is_none())
+            })
+            .collect();
+        moves.sort_by_key(|position| (position.row, position.column));
+        moves.dedup();
+        moves
+    }
+
+    /// Returns a line the token would complete by playing on the empty position, if there is one.
+    pub fn winning_line(&self, token: Token, position: Position) -> Option<Line> {
+        self.open_lines(token).into_iter().find(|line| {
+            line.contains(position) && self.count_on_line(line, token) + 1 == line.len()
+        })
+    }
+
+    /// Returns the positions the token must take to stop its opponent winning on the next move.
+    ///
+    /// If there are two or more, the opponent has a fork, and cannot be stopped.
+    pub fn must_block(&self, token: Token) -> Vec<Position> {
+        self.winning_moves(token.opponent())
+    }
+
+    /// Returns the winning moves the token would have after also playing on the empty position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the position is not on the board.
+    ///
+    /// Panics if the position is already occupied.
+    pub fn winning_moves_after(&self, token: Token, position: Position) -> Vec<Position> {
+        if !self.contains(position) {
+            panic!(
+                "{}",
+                MoveError::OutOfRange {
+                    row: position.row,
+                    column: position.column
+                }
+            );
+        }
+        if self.get(position).is_some() {
+            panic!("{}", MoveError::Occupied(position));
+        }
+        let mut board = *self;
+        board.set(position, Some(token));
+        board.winning_moves(token)
+    }
+
+    /// Returns the empty positions where the token would create a fork:
+    /// two or more ways to win on its next move.
+    ///
+    /// # Examples
+    /// ```
+    /// use tictactoelib::board::{Board, Position, Token};
+    /// let board: Board = "X--/-O-/--X".parse().unwrap();
+    /// assert_eq!(
+    ///     board.fork_moves(Token::X),
+    ///     vec![Position::new(0, 2), Position::new(2, 0)]
+    /// );
+    /// ```
+    pub fn fork_moves(&self, token: Token) -> Vec<Position> {
+        self.empty_positions_iter()
+            .filter(|position| self.winning_moves_after(token, *position).len() >= 2)
+            .collect()
+    }
+
+    /// Returns the empty positions where the token stops all of its opponent's forks,
+    /// or an empty list if the opponent has no fork to stop.
+    ///
+    /// A move stops the forks if, afterwards, the opponent has no fork left,
+    /// or has to block a threat on a position that does not give them one.
+    ///
+    /// # Examples
+    /// ```
+    /// use tictactoelib::board::{Board, Position, Token};
+    /// let board: Board = "X--/-O-/--X".parse().unwrap();
+    /// // Taking a corner forces X to block on the opposite corner, where X forks.
+    /// assert_eq!(
+    ///     board.fork_blocking_moves(Token::O),
+    ///     vec![
+    ///         Position::new(0, 1),
+    ///         Position::new(1, 0),
+    ///         Position::new(1, 2),
+    ///         Position::new(2, 1)
+    ///     ]
+    /// );
+    /// ```
+    pub fn fork_blocking_moves(&self, token: Token) -> Vec<Position> {
+        let opponent = token.opponent();
+        if self.fork_moves(opponent).is_empty() {
+            return Vec::new();
+        }
+        self.empty_positions_iter()
+            .filter(|position| {
+                let mut board = *self;
+                board.set(*position, Some(token));
+                let threats = board.winning_moves(token);
+                match threats.len() {
+                    0 => board.fork_moves(opponent).is_empty(),
+                    1 => {
+                        board.set(threats[0], Some(opponent));
+                        board.winning_moves(opponent).len() < 2
+                    }
+                    _ => true,
+                }
+            })
+            .collect()
+    }
+
+    #[doc(hidden)]
+    fn count_on_line(&self, line: &Line, token: Token) -> u8 {
+        line.positions()
+            .filter(|position| self.get(*position) == Some(token))
+            .count() as u8
+    }
+
+    /// Returns the board with every position, including the recorded moves, transformed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the transform does not fit the board.
+    pub fn transformed(&self, transform: Transform) -> Board {
+        let mut board = *self;
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let from = Position { row, column };
+                let to = transform.apply(from, self);
+                board.set(to, self.get(from));
+            }
+        }
+        let recorded = (self.undo_count + self.redo_count) as usize;
+        for index in board.history[..recorded].iter_mut() {
+            *index = self.index_of(transform.apply(self.position_of(*index), self));
+        }
+        board
+    }
+
+    /// Returns the transforms that map this board's shape onto itself.
+    pub fn symmetries(&self) -> impl Iterator<Item = Transform> + '_ {
+        Transform::ALL
+            .iter()
+            .copied()
+            .filter(move |transform| transform.fits(self))
+    }
+
+    /// Returns the canonical form of the board, along with the transform that produces it.
+    ///
+    /// The canonical form is the symmetric equivalent that comes first when the positions
+    /// are read row by row, with empty < X < O, so boards that are reflections or rotations
+    /// of each other all share one canonical form.
+    ///
+    /// Examples
+    /// ```
+    /// use tictactoelib::board::{Board, Position, Token};
+    /// let mut top_left = Board::new();
+    /// top_left.add_move(Token::X, Position::new(0, 0));
+    /// let mut bottom_right = Board::new();
+    /// bottom_right.add_move(Token::X, Position::new(2, 2));
+    ///
+    /// let (canonical, transform) = bottom_right.canonical();
+    /// assert_eq!(canonical, top_left.canonical().0);
+    /// assert_eq!(bottom_right.transformed(transform), canonical);
+    /// ```
+    pub fn canonical(&self) -> (Board, Transform) {
+        let mut best = (*self, Transform::Identity);
+        let mut best_key = self.ordering_key(Transform::Identity);
+        for transform in self.symmetries().skip(1) {
+            let key = self.ordering_key(transform);
+            if key < best_key {
+                best = (self.transformed(transform), transform);
+                best_key = key;
+            }
+        }
+        best
+    }
+
+    /// Returns the positions of the transformed board, for ordering boards.
+    ///
+    /// Each row is packed into a number, two bits per position with the first column highest,
+    /// so comparing keys compares the positions row by row.
+    #[doc(hidden)]
+    fn ordering_key(&self, transform: Transform) -> [u32; MAX_SIZE as usize] {
+        let mut key = [0; MAX_SIZE as usize];
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let from = Position { row, column };
+                let code = match self.get(from) {
+                    None => continue,
+                    Some(Token::X) => 1,
+                    Some(Token::O) => 2,
+                };
+                let to = transform.apply(from, self);
+                key[to.row as usize] |= code << (2 * (self.columns - 1 - to.column));
+            }
+        }
+        key
+    }
+
+    #[doc(hidden)]
+    #[cfg(debug_assertions)]
+    fn check_invariants(&self) {
+        let _winner = self.get_game_result();
+        if self.turn_number == 0 || self.turn_number as usize > self.cell_count() + 1 {
+            panic!("Invalid turn number {}!", self.turn_number);
+        }
+    }
+} // impl Board
+
+impl PartialEq for Board {
+    fn eq(&self, other: &Board) -> bool {
+        self.rows == other.rows
+            && self.columns == other.columns
+            && self.run_length == other.run_length
+            && self.rules == other.rules
+            && self.turn_number == other.turn_number
+            && self.x_rows == other.x_rows
+            && self.o_rows == other.o_rows
+    }
+}
+
+impl Eq for Board {}
+
+impl std::fmt::Debug for Board {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The unused parts of the arrays are just noise.
+        f.debug_struct("Board")
+            .field("positions", &self.to_compact_string())
+            .field("run_length", &self.run_length)
+            .field("rules", &self.rules)
+            .field("turn_number", &self.turn_number)
+            .field("moves", &self.moves())
+            .finish()
+    }
+}
+
+impl std::hash::Hash for Board {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        // Must agree with PartialEq, so the move history is left out.
+        self.rows.hash(state);
+        self.columns.hash(state);
+        self.run_length.hash(state);
+        self.rules.hash(state);
+        self.turn_number.hash(state);
+        self.x_rows[..self.rows as usize].hash(state);
+        self.o_rows[..self.rows as usize].hash(state);
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+/// One of the eight symmetries of a square board: rotations (clockwise) and reflections.
+///
+/// Rectangular boards only have Identity, Rotate180, FlipHorizontal and FlipVertical.
+pub enum Transform {
+    Identity,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    /// Mirrors left and right.
+    FlipHorizontal,
+    /// Mirrors top and bottom.
+    FlipVertical,
+    /// Mirrors across the '\' diagonal.
+    FlipDiagonal,
+    /// Mirrors across the '/' diagonal.
+    FlipAntiDiagonal,
+}
+
+impl Transform {
+    /// Every transform, starting with Identity.
+    pub const ALL: [Transform; 8] = [
+        Transform::Identity,
+        Transform::Rotate90,
+        Transform::Rotate180,
+        Transform::Rotate270,
+        Transform::FlipHorizontal,
+        Transform::FlipVertical,
+        Transform::FlipDiagonal,
+        Transform::FlipAntiDiagonal,
+    ];
+
+    /// Returns the transform that undoes this one.
+    pub fn inverse(self) -> Transform {
+        match self {
+            Transform::Rotate90 => Transform::Rotate270,
+            Transform::Rotate270 => Transform::Rotate90,
+            other => other,
+        }
+    }
+
+    /// Indicates whether or not the transform maps the given board onto itself,
+    /// i.e. the board is square, or the transform does not swap rows and columns.
+    pub fn fits(self, board: &Board) -> bool {
+        board.rows == board.columns
+            || matches!(
+                self,
+                Transform::Identity
+                    | Transform::Rotate180
+                    | Transform::FlipHorizontal
+                    | Transform::FlipVertical
+            )
+    }
+
+    /// Returns where the given position on the board ends up after the transform.
+    ///
+    /// Examples
+    /// ```
+    /// use tictactoelib::board::{Board, Position, Transform};
+    /// let board = Board::new();
+    /// let top_left = Position::new(0, 0);
+    /// assert_eq!(Transform::Rotate90.apply(top_left, &board), Position::new(0, 2));
+    /// assert_eq!(Transform::Rotate90.invert(Position::new(0, 2), &board), top_left);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the transform does not fit the board.
+    pub fn apply(self, position: Position, board: &Board) -> Position {
+        if !self.fits(board) {
+            panic!(
+                "{:?} does not fit a {}x{} board!",
+                self, board.rows, board.columns
+            );
+        }
+        let (row, column) = (position.row, position.column);
+        let last_row = board.rows - 1;
+        let last_column = board.columns - 1;
+        let (row, column) = match self {
+            Transform::Identity => (row, column),
+            Transform::Rotate90 => (column, last_row - row),
+            Transform::Rotate180 => (last_row - row, last_column - column),
+            Transform::Rotate270 => (last_column - column, row),
+            Transform::FlipHorizontal => (row, last_column - column),
+            Transform::FlipVertical => (last_row - row, column),
+            Transform::FlipDiagonal => (column, row),
+            Transform::FlipAntiDiagonal => (last_column - column, last_row - row),
+        };
+        Position { row, column }
+    }
+
+    /// Returns the position that the transform moves onto the given position,
+    /// i.e. undoes apply().
+    pub fn invert(self, position: Position, board: &Board) -> Position {
+        self.inverse().apply(position, board)
+    }
+}
+
+impl std::fmt::Display for Board {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Will look something like:
+        // ┌───┐
+        // |X O|
+        // | X |
+        // |OOX|
         // └───┘
-        write!(
-            f,
-            "┌───┐\n│{}{}{}│\n│{}{}{}│\n│{}{}{}│\n└───┘",
-            match self.positions[0][0] {
-                Some(Token::X) => "X",
-                Some(Token::O) => "O",
-                None => " ",
-            },
-            match self.positions[0][1] {
-                Some(Token::X) => "X",
-                Some(Token::O) => "O",
-                None => " ",
-            },
-            match self.positions[0][2] {
-                Some(Token::X) => "X",
-                Some(Token::O) => "O",
-                None => " ",
-            },
-            match self.positions[1][0] {
-                Some(Token::X) => "X",
-                Some(Token::O) => "O",
-                None => " ",
-            },
-            match self.positions[1][1] {
-                Some(Token::X) => "X",
-                Some(Token::O) => "O",
-                None => " ",
-            },
-            match self.positions[1][2] {
-                Some(Token::X) => "X",
-                Some(Token::O) => "O",
-                None => " ",
-            },
-            match self.positions[2][0] {
-                Some(Token::X) => "X",
-                Some(Token::O) => "O",
-                None => " ",
-            },
-            match self.positions[2][1] {
-                Some(Token::X) => "X",
-                Some(Token::O) => "O",
-                None => " ",
-            },
-            match self.positions[2][2] {
-                Some(Token::X) => "X",
-                Some(Token::O) => "O",
-                None => " ",
+        let border = "─".repeat(self.columns as usize);
+        writeln!(f, "┌{}┐", border)?;
+        for row in 0..self.rows {
+            write!(f, "│")?;
+            for column in 0..self.columns {
+                match self.get(Position { row, column }) {
+                    Some(Token::X) => write!(f, "X")?,
+                    Some(Token::O) => write!(f, "O")?,
+                    None => write!(f, " ")?,
+                }
             }
-        )
+            writeln!(f, "│")?;
+        }
+        write!(f, "└{}┘", border)
     } // fn fmt()
 } // impl std::fmt::Display for Board
 
+impl std::str::FromStr for Board {
+    type Err = ParseBoardError;
+
+    /// Parses a board in compact form ("XO-OO-XX-"),
+    /// or as a grid with rows separated by whitespace or newlines.
+    ///
+    /// The board is won by filling a row, column or diagonal;
+    /// use Board::parse_with_run_length() for other run lengths.
+    ///
+    /// Examples
+    /// ```
+    /// use tictactoelib::board::{Board, Token};
+    /// let board: Board = "XO-\n\
+    ///                     -X-\n\
+    ///                     -O-".parse().unwrap();
+    /// assert_eq!(board.whose_turn(), Some(Token::X));
+    /// assert_eq!(board, "XO--X--O-".parse().unwrap());
+    /// ```
+    fn from_str(contents: &str) -> Result<Board, ParseBoardError> {
+        let rows: Vec<&str> = contents
+            .split(|the_char: char| the_char.is_whitespace() || the_char == '/')
+            .filter(|row| !row.is_empty())
+            .collect();
+        Board::from_rows(&rows, None)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// The reasons a board can fail to parse.
+pub enum ParseBoardError {
+    /// A character other than 'X', 'O' or '-' was found.
+    InvalidCharacter(char),
+    /// A single run of characters of this length does not make a square board.
+    InvalidLength(usize),
+    /// The rows do not all have the same length.
+    RaggedRows { expected: usize, found: usize },
+    /// The board has no positions, or is larger than MAX_SIZE x MAX_SIZE.
+    InvalidSize { rows: usize, columns: usize },
+    /// The run length needed to win does not fit on the board.
+    InvalidRunLength(u8),
+    /// X must have the same number of moves as O, or one more.
+    InvalidTokenCount { x_count: usize, o_count: usize },
+    /// Both X and O have a winning line.
+    MultipleWinners,
+    /// The given Token had already won when another move was played.
+    PlayedAfterWin(Token),
+}
+
+impl std::fmt::Display for ParseBoardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseBoardError::InvalidCharacter(value) => {
+                write!(f, "Invalid character for player: '{}'", value)
+            }
+            ParseBoardError::InvalidLength(length) => {
+                write!(f, "Invalid string length {} for board", length)
+            }
+            ParseBoardError::RaggedRows { expected, found } => write!(
+                f,
+                "Invalid row length {}, expected {} like the first row",
+                found, expected
+            ),
+            ParseBoardError::InvalidSize { rows, columns } => {
+                write!(f, "Invalid board size: {}x{}", rows, columns)
+            }
+            ParseBoardError::InvalidRunLength(run_length) => {
+                write!(f, "Invalid run length: {}", run_length)
+            }
+            ParseBoardError::InvalidTokenCount { x_count, o_count } => write!(
+                f,
+                "Invalid number of Xs and Os! ({} Xs, {} Os)",
+                x_count, o_count
+            ),
+            ParseBoardError::MultipleWinners => write!(f, "Game cannot have multiple winners!"),
+            ParseBoardError::PlayedAfterWin(winner) => write!(
+                f,
+                "Unreachable board: moves were played after {} had already won!",
+                winner
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseBoardError {}
+
 #[derive(Debug, PartialEq)]
 /// Indicates the result of a game.
 pub enum GameResult {
@@ -340,6 +1432,12 @@ mod player_tests {
         assert_eq!(player1, player3);
     }
 
+    #[test]
+    fn test_player_opponent() {
+        assert_eq!(Token::X.opponent(), Token::O);
+        assert_eq!(Token::O.opponent(), Token::X);
+    }
+
     #[test]
     fn test_player_display() {
         let x = Token::X;
@@ -364,15 +1462,31 @@ mod position_tests {
     }
 
     #[test]
-    #[should_panic(expected = "Invalid row: 3")]
+    #[should_panic(expected = "Position [16,0] is out of range!")]
     fn test_position_ctor_invalid_row() {
-        let _position = Position::new(3, 0);
+        let _position = Position::new(16, 0);
     }
 
     #[test]
-    #[should_panic(expected = "Invalid column: 3")]
+    #[should_panic(expected = "Position [0,16] is out of range!")]
     fn test_position_ctor_invalid_column() {
-        let _position = Position::new(0, 3);
+        let _position = Position::new(0, 16);
+    }
+
+    #[test]
+    fn test_position_try_new() {
+        assert_eq!(Position::try_new(1, 2), Ok(Position::new(1, 2)));
+        assert_eq!(
+            Position::try_new(16, 0),
+            Err(MoveError::OutOfRange { row: 16, column: 0 })
+        );
+        assert_eq!(
+            Position::try_new(0, 255),
+            Err(MoveError::OutOfRange {
+                row: 0,
+                column: 255
+            })
+        );
     }
 } // mod position_tests
 
@@ -384,7 +1498,7 @@ mod board_tests {
         let empty = Board::new();
         for row in 0..2 {
             for column in 0..2 {
-                assert_eq!(None, empty.positions[row][column]);
+                assert_eq!(None, empty.get(Position::new(row, column)));
             }
         }
     }
@@ -396,7 +1510,7 @@ mod board_tests {
              OXO\
              XOX",
         );
-        assert_eq!(Some(Token::X), full_board.positions[0][0]);
+        assert_eq!(Some(Token::X), full_board.get(Position::new(0, 0)));
         assert_eq!(10, full_board.turn_number);
 
         let empty_board = Board::from_string(
@@ -404,7 +1518,7 @@ mod board_tests {
              ---\
              ---",
         );
-        assert_eq!(None, empty_board.positions[0][0]);
+        assert_eq!(None, empty_board.get(Position::new(0, 0)));
         assert_eq!(1, empty_board.turn_number);
         assert_eq!(Board::new(), empty_board);
     }
@@ -457,9 +1571,9 @@ mod board_tests {
              XXO",
         );
         assert_eq!(full_board.empty_positions(), Vec::new());
-        assert_eq!(false, full_board.is_position_unused(Position::new(0, 0)));
-        assert_eq!(false, full_board.is_position_unused(Position::new(2, 2)));
-        assert_eq!(false, full_board.is_position_unused(Position::new(2, 2)));
+        assert!(!full_board.is_position_unused(Position::new(0, 0)));
+        assert!(!full_board.is_position_unused(Position::new(2, 2)));
+        assert!(!full_board.is_position_unused(Position::new(2, 2)));
     }
 
     #[test]
@@ -470,8 +1584,11 @@ mod board_tests {
              XXO",
         );
         assert_eq!(one_left.empty_positions(), vec![Position::new(1, 2)]);
-        assert_eq!(true, one_left.is_position_unused(Position::new(1, 2)));
-        assert_eq!(false, one_left.is_position_unused(Position::new(2, 2)));
+        assert!(one_left.is_position_unused(Position::new(1, 2)));
+        assert!(!one_left.is_position_unused(Position::new(2, 2)));
+        assert_eq!(one_left.token_at(Position::new(1, 2)), None);
+        assert_eq!(one_left.token_at(Position::new(2, 2)), Some(Token::O));
+        assert_eq!(one_left.token_at(Position::new(2, 1)), Some(Token::X));
     }
 
     #[test]
@@ -485,9 +1602,9 @@ mod board_tests {
             two_left.empty_positions(),
             vec![Position::new(0, 1), Position::new(2, 0)]
         );
-        assert_eq!(true, two_left.is_position_unused(Position::new(0, 1)));
-        assert_eq!(true, two_left.is_position_unused(Position::new(2, 0)));
-        assert_eq!(false, two_left.is_position_unused(Position::new(2, 2)));
+        assert!(two_left.is_position_unused(Position::new(0, 1)));
+        assert!(two_left.is_position_unused(Position::new(2, 0)));
+        assert!(!two_left.is_position_unused(Position::new(2, 2)));
     }
 
     #[test]
@@ -539,11 +1656,11 @@ mod board_tests {
         let mut board = Board::new();
 
         board.add_move(Token::X, Position::new(1, 1));
-        assert_eq!(Some(Token::X), board.positions[1][1]);
+        assert_eq!(Some(Token::X), board.get(Position::new(1, 1)));
         assert_eq!(2, board.turn_number);
 
         board.add_move(Token::O, Position::new(0, 0));
-        assert_eq!(Some(Token::O), board.positions[0][0]);
+        assert_eq!(Some(Token::O), board.get(Position::new(0, 0)));
         assert_eq!(3, board.turn_number);
 
         board.add_move(Token::X, Position::new(0, 1));
@@ -555,14 +1672,14 @@ mod board_tests {
         assert_eq!(9, board.turn_number);
         board.add_move(Token::X, Position::new(2, 2));
 
-        assert_eq!(Some(Token::X), board.positions[0][1]);
-        assert_eq!(Some(Token::X), board.positions[1][0]);
-        assert_eq!(Some(Token::X), board.positions[2][0]);
-        assert_eq!(Some(Token::X), board.positions[2][2]);
+        assert_eq!(Some(Token::X), board.get(Position::new(0, 1)));
+        assert_eq!(Some(Token::X), board.get(Position::new(1, 0)));
+        assert_eq!(Some(Token::X), board.get(Position::new(2, 0)));
+        assert_eq!(Some(Token::X), board.get(Position::new(2, 2)));
 
-        assert_eq!(Some(Token::O), board.positions[0][2]);
-        assert_eq!(Some(Token::O), board.positions[1][2]);
-        assert_eq!(Some(Token::O), board.positions[2][1]);
+        assert_eq!(Some(Token::O), board.get(Position::new(0, 2)));
+        assert_eq!(Some(Token::O), board.get(Position::new(1, 2)));
+        assert_eq!(Some(Token::O), board.get(Position::new(2, 1)));
     }
 
     #[test]
@@ -609,6 +1726,41 @@ mod board_tests {
         assert_eq!(format!("{}", board), "┌───┐\n│XOX│\n│   │\n│OOX│\n└───┘");
     }
 
+    #[test]
+    fn test_misere_game() {
+        let mut board = Board::from_string(
+            "XXX\
+             OO-\
+             ---",
+        );
+        board.set_rules(Rules::Misere);
+        assert_eq!(board.get_game_result(), GameResult::Win(Token::O));
+        assert_eq!(board.whose_turn(), None);
+
+        let mut board = Board::from_string(
+            "XX-\
+             OOO\
+             X--",
+        );
+        board.set_rules(Rules::Misere);
+        assert_eq!(board.get_game_result(), GameResult::Win(Token::X));
+
+        let mut board = Board::from_string(
+            "XOX\
+             XOO\
+             OXX",
+        );
+        board.set_rules(Rules::Misere);
+        assert_eq!(board.get_game_result(), GameResult::Draw);
+
+        // The rules are part of the position, and survive transformation.
+        let misere = Board::misere();
+        assert_ne!(misere, Board::new());
+        assert_eq!(Board::new().rules(), Rules::Standard);
+        let (canonical, _transform) = misere.canonical();
+        assert_eq!(canonical.rules(), Rules::Misere);
+    }
+
     #[test]
     fn test_winning_game() {
         let mut board = Board::new();
@@ -700,3 +1852,728 @@ mod board_tests {
         assert_eq!(board.get_game_result(), GameResult::InProgress);
     }
 } // mod board_tests
+
+#[cfg(test)]
+mod board_size_tests {
+    use super::*;
+
+    fn play(board: &mut Board, moves: &[(u8, u8)]) {
+        for &(row, column) in moves {
+            board.add_move(board.whose_turn().unwrap(), Position::new(row, column));
+        }
+    }
+
+    #[test]
+    fn test_board_dimensions() {
+        let board = Board::new();
+        assert_eq!(
+            (board.rows(), board.columns(), board.run_length()),
+            (3, 3, 3)
+        );
+
+        let board = Board::with_size(4, 5, 4);
+        assert_eq!(
+            (board.rows(), board.columns(), board.run_length()),
+            (4, 5, 4)
+        );
+        assert_eq!(board.empty_positions().len(), 20);
+        assert!(board.contains(Position::new(3, 4)));
+        assert!(!board.contains(Position::new(4, 4)));
+        assert!(!board.is_position_unused(Position::new(3, 5)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid board size: 17x3")]
+    fn test_board_too_large() {
+        let _board = Board::with_size(17, 3, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid run length: 5")]
+    fn test_run_length_too_long() {
+        let _board = Board::with_size(4, 4, 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "Position [3,0] is out of range!")]
+    fn test_move_off_board() {
+        let mut board = Board::new();
+        board.add_move(Token::X, Position::new(3, 0));
+    }
+
+    #[test]
+    fn test_four_by_four_row() {
+        let mut board = Board::with_size(4, 4, 4);
+        play(
+            &mut board,
+            &[(0, 0), (1, 0), (0, 1), (1, 1), (0, 2), (1, 2)],
+        );
+        assert_eq!(board.get_game_result(), GameResult::InProgress);
+        assert_eq!(board.whose_turn(), Some(Token::X));
+        play(&mut board, &[(0, 3)]);
+        assert_eq!(board.get_game_result(), GameResult::Win(Token::X));
+        assert_eq!(board.whose_turn(), None);
+    }
+
+    #[test]
+    fn test_five_by_five_diagonal() {
+        let mut board = Board::with_size(5, 5, 4);
+        play(
+            &mut board,
+            &[(0, 0), (1, 4), (0, 1), (2, 3), (4, 4), (3, 2), (0, 2)],
+        );
+        assert_eq!(board.get_game_result(), GameResult::InProgress);
+        play(&mut board, &[(4, 1)]);
+        assert_eq!(board.get_game_result(), GameResult::Win(Token::O));
+    }
+
+    #[test]
+    fn test_gomoku_column() {
+        let mut board = Board::with_size(15, 15, 5);
+        play(
+            &mut board,
+            &[
+                (7, 7),
+                (0, 0),
+                (8, 7),
+                (0, 1),
+                (9, 7),
+                (0, 2),
+                (10, 7),
+                (0, 3),
+            ],
+        );
+        assert_eq!(board.get_game_result(), GameResult::InProgress);
+        play(&mut board, &[(11, 7)]);
+        assert_eq!(board.get_game_result(), GameResult::Win(Token::X));
+    }
+
+    #[test]
+    fn test_largest_board_edges() {
+        let mut board = Board::with_size(16, 16, 16);
+        for row in 0..15 {
+            play(&mut board, &[(row, 15), (row, 14)]);
+        }
+        assert_eq!(board.get_game_result(), GameResult::InProgress);
+        assert_eq!(board.empty_positions_iter().count(), 256 - 30);
+        play(&mut board, &[(15, 15)]);
+        assert_eq!(board.get_game_result(), GameResult::Win(Token::X));
+    }
+
+    #[test]
+    fn test_rectangular_board_draw() {
+        let mut board = Board::with_size(2, 3, 3);
+        play(&mut board, &[(0, 0), (0, 1), (1, 1), (1, 0), (0, 2)]);
+        assert_eq!(board.get_game_result(), GameResult::InProgress);
+        play(&mut board, &[(1, 2)]);
+        assert_eq!(board.get_game_result(), GameResult::Draw);
+    }
+
+    #[test]
+    fn test_rectangular_board_display() {
+        let mut board = Board::with_size(2, 4, 3);
+        play(&mut board, &[(0, 0), (1, 3)]);
+        assert_eq!(format!("{}", board), "┌────┐\n│X   │\n│   O│\n└────┘");
+    }
+} // mod board_size_tests
+
+#[cfg(test)]
+mod move_error_tests {
+    use super::*;
+
+    #[test]
+    fn test_try_add_move_success() {
+        let mut board = Board::new();
+        assert_eq!(board.try_add_move(Token::X, Position::new(1, 1)), Ok(()));
+        assert_eq!(board.whose_turn(), Some(Token::O));
+    }
+
+    #[test]
+    fn test_try_add_move_errors_leave_board_unchanged() {
+        let mut board = Board::new();
+        board.add_move(Token::X, Position::new(1, 1));
+        let before = board;
+
+        assert_eq!(
+            board.try_add_move(Token::O, Position::new(1, 1)),
+            Err(MoveError::Occupied(Position::new(1, 1)))
+        );
+        assert_eq!(
+            board.try_add_move(Token::X, Position::new(0, 0)),
+            Err(MoveError::OutOfTurn(Token::X))
+        );
+        assert_eq!(
+            board.try_add_move(Token::O, Position::new(3, 0)),
+            Err(MoveError::OutOfRange { row: 3, column: 0 })
+        );
+        assert_eq!(board, before);
+    }
+
+    #[test]
+    fn test_try_add_move_game_over() {
+        let mut board = Board::from_string(
+            "XXX\
+             OO-\
+             ---",
+        );
+        assert_eq!(
+            board.try_add_move(Token::O, Position::new(1, 2)),
+            Err(MoveError::GameOver)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The game is already over!")]
+    fn test_add_move_game_over() {
+        let mut board = Board::from_string(
+            "XXX\
+             OO-\
+             ---",
+        );
+        board.add_move(Token::O, Position::new(1, 2));
+    }
+
+    #[test]
+    fn test_try_position() {
+        let board = Board::with_size(4, 5, 4);
+        assert_eq!(board.try_position(3, 4), Ok(Position::new(3, 4)));
+        assert_eq!(
+            board.try_position(4, 4),
+            Err(MoveError::OutOfRange { row: 4, column: 4 })
+        );
+        assert_eq!(
+            board.try_position(0, 20),
+            Err(MoveError::OutOfRange { row: 0, column: 20 })
+        );
+    }
+
+    #[test]
+    fn test_move_error_is_error() {
+        let error: Box<dyn std::error::Error> = Box::new(MoveError::OutOfTurn(Token::O));
+        assert_eq!(error.to_string(), "It is not O's turn!");
+        assert_eq!(
+            MoveError::Occupied(Position::new(0, 2)).to_string(),
+            "Position [0,2] is already occupied!"
+        );
+    }
+} // mod move_error_tests
+
+#[cfg(test)]
+mod parse_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_compact() {
+        let board: Board = "XO-OO-XX-".parse().unwrap();
+        assert_eq!(board, Board::from_string("XO-OO-XX-"));
+        assert_eq!(board.whose_turn(), Some(Token::X));
+        assert_eq!(board.empty_positions().len(), 3);
+    }
+
+    #[test]
+    fn test_parse_grid() {
+        let grid: Board = "XO-\nOO-\nXX-\n".parse().unwrap();
+        let spaced: Board = " XO- OO-  XX- ".parse().unwrap();
+        let slashed: Board = "XO-/OO-/XX-".parse().unwrap();
+        assert_eq!(grid, Board::from_string("XO-OO-XX-"));
+        assert_eq!(spaced, grid);
+        assert_eq!(slashed, grid);
+    }
+
+    #[test]
+    fn test_parse_other_sizes() {
+        let board: Board = "X--- -O-- --X- ----".parse().unwrap();
+        assert_eq!(
+            (board.rows(), board.columns(), board.run_length()),
+            (4, 4, 4)
+        );
+        assert_eq!(board.whose_turn(), Some(Token::O));
+
+        let board: Board = "XO- ---".parse().unwrap();
+        assert_eq!(
+            (board.rows(), board.columns(), board.run_length()),
+            (2, 3, 2)
+        );
+
+        let board = Board::parse_with_run_length("XXX- OO-- ---- ----", 3).unwrap();
+        assert_eq!(board.get_game_result(), GameResult::Win(Token::X));
+    }
+
+    #[test]
+    fn test_compact_round_trip() {
+        for compact in &[
+            "---------",
+            "X--------",
+            "XOXXOOOXX",
+            "XOX-O-X-O",
+            "X---/-O--",
+        ] {
+            let board = Board::from_compact_str(compact).unwrap();
+            assert_eq!(&board.to_compact_string(), compact);
+            let reparsed: Board = board.to_compact_string().parse().unwrap();
+            assert_eq!(reparsed, board);
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            "XO-OO-XX?".parse::<Board>(),
+            Err(ParseBoardError::InvalidCharacter('?'))
+        );
+        assert_eq!(
+            Board::from_compact_str("XO- OO-XX"),
+            Err(ParseBoardError::InvalidCharacter(' '))
+        );
+        assert_eq!(
+            "XO-OO-XX".parse::<Board>(),
+            Err(ParseBoardError::InvalidLength(8))
+        );
+        assert_eq!("".parse::<Board>(), Err(ParseBoardError::InvalidLength(0)));
+        assert_eq!(
+            Board::parse_with_run_length("", 3),
+            Err(ParseBoardError::InvalidLength(0))
+        );
+        assert_eq!(
+            Board::parse_with_run_length(" \n/ ", 3),
+            Err(ParseBoardError::InvalidLength(0))
+        );
+        assert_eq!(
+            "XO- OO XX-".parse::<Board>(),
+            Err(ParseBoardError::RaggedRows {
+                expected: 3,
+                found: 2
+            })
+        );
+        assert_eq!(
+            Board::parse_with_run_length("XO- ---", 4),
+            Err(ParseBoardError::InvalidRunLength(4))
+        );
+        assert_eq!(
+            "XXX------".parse::<Board>(),
+            Err(ParseBoardError::InvalidTokenCount {
+                x_count: 3,
+                o_count: 0
+            })
+        );
+        assert_eq!(
+            "OO-------".parse::<Board>(),
+            Err(ParseBoardError::InvalidTokenCount {
+                x_count: 0,
+                o_count: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_unreachable() {
+        assert_eq!(
+            "XXXOOO---".parse::<Board>(),
+            Err(ParseBoardError::MultipleWinners)
+        );
+        // X won, but O moved afterwards.
+        assert_eq!(
+            "XXXOO-O--".parse::<Board>(),
+            Err(ParseBoardError::PlayedAfterWin(Token::X))
+        );
+        // O won, but X moved afterwards.
+        assert_eq!(
+            "OOOXX-XX-".parse::<Board>(),
+            Err(ParseBoardError::PlayedAfterWin(Token::O))
+        );
+        // A single move can complete two lines at once.
+        assert_eq!(
+            "XXXOOXOOX".parse::<Board>(),
+            Ok(Board::from_string("XXXOOXOOX"))
+        );
+        assert_eq!(
+            Board::parse_with_run_length("XXX- OOO- XXX- -OO-", 3),
+            Err(ParseBoardError::MultipleWinners)
+        );
+        assert_eq!(
+            Board::parse_with_run_length("XXXO OO-- XX-- -O-O", 3),
+            Err(ParseBoardError::PlayedAfterWin(Token::X))
+        );
+    }
+} // mod parse_tests
+
+#[cfg(test)]
+mod history_tests {
+    use super::*;
+
+    #[test]
+    fn test_moves_recorded_in_order() {
+        let mut board = Board::new();
+        assert_eq!(board.moves(), Vec::new());
+        assert_eq!(board.last_move(), None);
+
+        board.add_move(Token::X, Position::new(1, 1));
+        board.add_move(Token::O, Position::new(0, 2));
+        board.add_move(Token::X, Position::new(2, 0));
+        assert_eq!(
+            board.moves(),
+            vec![
+                Position::new(1, 1),
+                Position::new(0, 2),
+                Position::new(2, 0)
+            ]
+        );
+        assert_eq!(board.last_move(), Some(Position::new(2, 0)));
+    }
+
+    #[test]
+    fn test_undo_and_redo() {
+        let mut board = Board::new();
+        board.add_move(Token::X, Position::new(1, 1));
+        board.add_move(Token::O, Position::new(0, 0));
+        let two_moves = board;
+
+        assert_eq!(board.undo_move(), Some(Position::new(0, 0)));
+        assert_eq!(board.whose_turn(), Some(Token::O));
+        assert!(board.is_position_unused(Position::new(0, 0)));
+        assert_eq!(board.undo_move(), Some(Position::new(1, 1)));
+        assert_eq!(board.undo_move(), None);
+        assert_eq!(board, Board::new());
+
+        assert_eq!(board.redo_move(), Some(Position::new(1, 1)));
+        assert_eq!(board.redo_move(), Some(Position::new(0, 0)));
+        assert_eq!(board.redo_move(), None);
+        assert_eq!(board, two_moves);
+        assert_eq!(board.moves(), two_moves.moves());
+    }
+
+    #[test]
+    fn test_new_move_discards_redo() {
+        let mut board = Board::new();
+        board.add_move(Token::X, Position::new(1, 1));
+        board.add_move(Token::O, Position::new(0, 0));
+        board.undo_move();
+        board.add_move(Token::O, Position::new(2, 2));
+        assert_eq!(board.redo_move(), None);
+        assert_eq!(
+            board.moves(),
+            vec![Position::new(1, 1), Position::new(2, 2)]
+        );
+    }
+
+    #[test]
+    fn test_undo_winning_move() {
+        let mut board = Board::from_string(
+            "XX-\
+             OO-\
+             ---",
+        );
+        board.add_move(Token::X, Position::new(0, 2));
+        assert_eq!(board.get_game_result(), GameResult::Win(Token::X));
+        assert_eq!(board.whose_turn(), None);
+
+        assert_eq!(board.undo_move(), Some(Position::new(0, 2)));
+        assert_eq!(board.get_game_result(), GameResult::InProgress);
+        assert_eq!(board.whose_turn(), Some(Token::X));
+
+        // Moves from before the board was parsed were not recorded.
+        assert_eq!(board.undo_move(), None);
+        assert_eq!(board.moves(), Vec::new());
+    }
+
+    #[test]
+    fn test_equality_ignores_move_order() {
+        let mut first = Board::new();
+        first.add_move(Token::X, Position::new(0, 0));
+        first.add_move(Token::O, Position::new(1, 1));
+        first.add_move(Token::X, Position::new(2, 2));
+
+        let mut second = Board::new();
+        second.add_move(Token::X, Position::new(2, 2));
+        second.add_move(Token::O, Position::new(1, 1));
+        second.add_move(Token::X, Position::new(0, 0));
+
+        assert_eq!(first, second);
+        assert_ne!(first.moves(), second.moves());
+    }
+
+    #[test]
+    fn test_history_on_large_board() {
+        let mut board = Board::with_size(15, 15, 5);
+        board.add_move(Token::X, Position::new(14, 14));
+        board.add_move(Token::O, Position::new(7, 3));
+        assert_eq!(
+            board.moves(),
+            vec![Position::new(14, 14), Position::new(7, 3)]
+        );
+        assert_eq!(board.undo_move(), Some(Position::new(7, 3)));
+        assert_eq!(board.undo_move(), Some(Position::new(14, 14)));
+    }
+} // mod history_tests
+
+#[cfg(test)]
+mod symmetry_tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_transforms_round_trip() {
+        let board = Board::with_size(4, 4, 4);
+        for &transform in Transform::ALL.iter() {
+            for position in board.empty_positions() {
+                let moved = transform.apply(position, &board);
+                assert!(board.contains(moved));
+                assert_eq!(transform.invert(moved, &board), position);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rectangular_symmetries() {
+        let board = Board::with_size(2, 3, 2);
+        assert_eq!(
+            board.symmetries().collect::<Vec<Transform>>(),
+            vec![
+                Transform::Identity,
+                Transform::Rotate180,
+                Transform::FlipHorizontal,
+                Transform::FlipVertical
+            ]
+        );
+        assert_eq!(Board::new().symmetries().count(), 8);
+    }
+
+    #[test]
+    #[should_panic(expected = "Rotate90 does not fit a 2x3 board!")]
+    fn test_transform_does_not_fit() {
+        let board = Board::with_size(2, 3, 2);
+        let _position = Transform::Rotate90.apply(Position::new(0, 0), &board);
+    }
+
+    #[test]
+    fn test_transformed_board() {
+        let mut board = Board::new();
+        board.add_move(Token::X, Position::new(0, 0));
+        board.add_move(Token::O, Position::new(0, 1));
+        let rotated = board.transformed(Transform::Rotate90);
+        assert_eq!(rotated, Board::from_string("--X--O---"));
+        assert_eq!(
+            rotated.moves(),
+            vec![Position::new(0, 2), Position::new(1, 2)]
+        );
+        assert_eq!(rotated.transformed(Transform::Rotate270), board);
+    }
+
+    #[test]
+    fn test_canonical() {
+        let board = Board::from_string("X-O-X----");
+        let (canonical, transform) = board.canonical();
+        for &other in Transform::ALL.iter() {
+            let (other_canonical, _transform) = board.transformed(other).canonical();
+            assert_eq!(other_canonical, canonical);
+        }
+        assert_eq!(board.transformed(transform), canonical);
+        assert_eq!(canonical, Board::from_string("----X-X-O"));
+
+        let (canonical, transform) = Board::new().canonical();
+        assert_eq!(canonical, Board::new());
+        assert_eq!(transform, Transform::Identity);
+    }
+
+    #[test]
+    fn test_first_moves_up_to_symmetry() {
+        let mut first_moves = HashSet::new();
+        for position in Board::new().empty_positions() {
+            let mut board = Board::new();
+            board.add_move(Token::X, position);
+            first_moves.insert(board.canonical().0);
+        }
+        // A corner, an edge and the center.
+        assert_eq!(first_moves.len(), 3);
+    }
+
+    #[test]
+    fn test_positions_up_to_symmetry() {
+        let mut positions = HashSet::new();
+        let mut canonical_positions = HashSet::new();
+        let mut to_visit = vec![Board::new()];
+        while let Some(board) = to_visit.pop() {
+            if !positions.insert(board) {
+                continue;
+            }
+            canonical_positions.insert(board.canonical().0);
+            if let Some(player) = board.whose_turn() {
+                for position in board.empty_positions() {
+                    let mut next_board = board;
+                    next_board.add_move(player, position);
+                    to_visit.push(next_board);
+                }
+            }
+        }
+        assert_eq!(positions.len(), 5478);
+        assert_eq!(canonical_positions.len(), 765);
+    }
+} // mod symmetry_tests
+
+#[cfg(test)]
+mod threat_tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_line_positions() {
+        let board = Board::new();
+        let lines: Vec<Line> = board.lines().collect();
+        assert_eq!(lines.len(), 8);
+        let anti_diagonal = lines
+            .iter()
+            .find(|line| line.direction() == Direction::AntiDiagonal)
+            .unwrap();
+        assert_eq!(anti_diagonal.start(), Position::new(0, 2));
+        assert_eq!(anti_diagonal.end(), Position::new(2, 0));
+        assert_eq!(
+            anti_diagonal.positions().collect::<Vec<Position>>(),
+            vec![
+                Position::new(0, 2),
+                Position::new(1, 1),
+                Position::new(2, 0)
+            ]
+        );
+        assert!(anti_diagonal.contains(Position::new(1, 1)));
+        assert!(!anti_diagonal.contains(Position::new(0, 0)));
+        assert_eq!(anti_diagonal.len(), 3);
+    }
+
+    #[test]
+    fn test_line_counts() {
+        // Rows, columns, then both diagonals.
+        assert_eq!(Board::with_size(3, 4, 3).lines().count(), 3 * 2 + 4 + 2 + 2);
+        assert_eq!(Board::with_size(5, 5, 4).lines().count(), 10 + 10 + 4 + 4);
+        assert_eq!(Board::with_size(1, 5, 5).lines().count(), 1);
+    }
+
+    #[test]
+    fn test_lines_agree_with_game_result() {
+        let mut to_visit = vec![Board::with_size(3, 4, 3)];
+        let mut seen = HashSet::new();
+        while let Some(board) = to_visit.pop() {
+            if seen.len() > 20000 || !seen.insert(board) {
+                continue;
+            }
+            let full_line = |token| {
+                board
+                    .lines()
+                    .any(|line| board.count_on_line(&line, token) == line.len())
+            };
+            let expected = match board.get_game_result() {
+                GameResult::Win(winner) => Some(winner),
+                _ => None,
+            };
+            assert_eq!(full_line(Token::X), expected == Some(Token::X));
+            assert_eq!(full_line(Token::O), expected == Some(Token::O));
+            if let Some(player) = board.whose_turn() {
+                for position in board.winning_moves(player) {
+                    let mut next = board;
+                    next.add_move(player, position);
+                    assert_eq!(next.get_game_result(), GameResult::Win(player));
+                }
+                for position in board.empty_positions() {
+                    let mut next = board;
+                    next.add_move(player, position);
+                    to_visit.push(next);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_open_lines() {
+        let board = Board::from_string(
+            "X--\
+             -O-\
+             ---",
+        );
+        // O in the center blocks every line through it, and X in the corner three of them.
+        assert_eq!(board.open_lines(Token::X).len(), 4);
+        assert_eq!(board.open_lines(Token::O).len(), 5);
+    }
+
+    #[test]
+    fn test_winning_moves_and_blocks() {
+        let board = Board::from_string(
+            "XX-\
+             OO-\
+             X--",
+        );
+        assert_eq!(board.winning_moves(Token::X), vec![Position::new(0, 2)]);
+        assert_eq!(board.winning_moves(Token::O), vec![Position::new(1, 2)]);
+        assert_eq!(board.must_block(Token::X), vec![Position::new(1, 2)]);
+        assert_eq!(board.must_block(Token::O), vec![Position::new(0, 2)]);
+        assert_eq!(
+            board.winning_line(Token::X, Position::new(0, 2)),
+            board.lines().next()
+        );
+        assert_eq!(board.winning_line(Token::X, Position::new(2, 2)), None);
+    }
+
+    #[test]
+    fn test_two_ways_to_one_square() {
+        let board = Board::from_string(
+            "X-X\
+             -O-\
+             X-O",
+        );
+        // Both the top row and the left column are won on a single square each.
+        assert_eq!(
+            board.winning_moves(Token::X),
+            vec![Position::new(0, 1), Position::new(1, 0)]
+        );
+    }
+
+    #[test]
+    fn test_forks() {
+        let board = Board::from_string(
+            "X-O\
+             -O-\
+             --X",
+        );
+        // X must block on 7, which also forks.
+        assert_eq!(board.must_block(Token::X), vec![Position::new(2, 0)]);
+        assert_eq!(board.fork_moves(Token::X), vec![Position::new(2, 0)]);
+        assert_eq!(
+            board.winning_moves_after(Token::X, Position::new(2, 0)),
+            vec![Position::new(1, 0), Position::new(2, 1)]
+        );
+        assert!(Board::new().fork_moves(Token::X).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Position [0,2] is already occupied!")]
+    fn test_winning_moves_after_occupied() {
+        let board = Board::from_string(
+            "X-O\
+             -O-\
+             --X",
+        );
+        board.winning_moves_after(Token::X, Position::new(0, 2));
+    }
+
+    #[test]
+    fn test_fork_blocking_moves() {
+        // X can fork on 1 or 9 after O's move, unless O forces X elsewhere.
+        let board = Board::from_string(
+            "--X\
+             -O-\
+             X--",
+        );
+        assert_eq!(
+            board.fork_moves(Token::X),
+            vec![Position::new(0, 0), Position::new(2, 2)]
+        );
+        assert_eq!(
+            board.fork_blocking_moves(Token::O),
+            vec![
+                Position::new(0, 1),
+                Position::new(1, 0),
+                Position::new(1, 2),
+                Position::new(2, 1)
+            ]
+        );
+        assert!(Board::new().fork_blocking_moves(Token::X).is_empty());
+    }
+} // mod threat_tests
@@ -0,0 +1,546 @@
+//! Parsing of the tictactoe binary's command line.
+use crate::computer::Difficulty;
+
+use std::str::FromStr;
+
+/// The help text, printed by `tictactoe help` and after a usage error.
+pub const USAGE: &str = "\
+Usage:
+    tictactoe [--level <LEVEL>]    Play X or O against the computer [default: perfect]
+    tictactoe play [OPTIONS]       Play one or more games between chosen players
+    tictactoe simulate [OPTIONS]   Play many games between computer players, on several threads
+    tictactoe analyze <BOARD>      Show the value of every move on a board of up to 16 squares
+    tictactoe ultimate [OPTIONS]   Play ultimate tic-tac-toe, on nine boards
+    tictactoe help                 Show this help
+
+Options for play:
+    --x <PLAYER>     Who plays X [default: human]
+    --o <PLAYER>     Who plays O [default: optimal]
+    --level <LEVEL>  How well computer players play: easy, medium, hard or perfect
+                     [default: medium]
+    --games <N>      Number of games to play [default: 1]
+    --quiet          Only print the final tally, not the boards
+    --seed <N>       Seed for the computer and random players, to make games reproducible
+    --misere         Play misère tic-tac-toe, where three in a row loses
+
+Options for simulate:
+    --x <PLAYER>     Who plays X, other than a human [default: random]
+    --o <PLAYER>     Who plays O, other than a human [default: random]
+    --level <LEVEL>  How well computer players play [default: medium]
+    --games <N>      Number of games to play [default: 10000]
+    --seed <N>       Seed for the players; the results depend only on this [default: 0]
+    --threads <N>    Number of threads [default: one per processor]
+
+Options for ultimate:
+    --x <PLAYER>     Who plays X: human or random [default: human]
+    --o <PLAYER>     Who plays O: human or random [default: random]
+    --seed <N>       Seed for the random players
+
+Players:
+    human            Enter moves at the keyboard
+    computer         Plays well, except for mistakes that depend on the level
+    optimal          Never loses
+    random           Chooses any empty square
+    center           Chooses at random, favouring the center
+    corners          Chooses at random, favouring the corners
+    heuristic        Follows simple rules of thumb, like a textbook human
+    mcts             Searches by playing thousands of random games each turn
+    alphabeta        Searches ahead with alpha-beta pruning, for up to a second
+
+A board is given in compact form, one character per square (X, O or -),
+row by row, e.g. \"XO-OO-XX-\". Rows may be separated with '/'.";
+
+/// The kinds of player that can be chosen on the command line.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PlayerKind {
+    Human,
+    /// A ComputerPlayer, which plays at the chosen level.
+    Computer,
+    Optimal,
+    Random,
+    /// A WeightedRandomPlayer that favours the center.
+    Center,
+    /// A WeightedRandomPlayer that favours the corners.
+    Corners,
+    /// A HeuristicPlayer, which follows Newell and Simon's rules.
+    Heuristic,
+    /// An MctsPlayer, which searches by Monte Carlo Tree Search.
+    Mcts,
+    /// An AlphaBetaPlayer, which searches with alpha-beta pruning.
+    AlphaBeta,
+}
+
+impl FromStr for PlayerKind {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<PlayerKind, CliError> {
+        match s {
+            "human" => Ok(PlayerKind::Human),
+            "computer" => Ok(PlayerKind::Computer),
+            "optimal" => Ok(PlayerKind::Optimal),
+            "random" => Ok(PlayerKind::Random),
+            "center" => Ok(PlayerKind::Center),
+            "corners" => Ok(PlayerKind::Corners),
+            "heuristic" => Ok(PlayerKind::Heuristic),
+            "mcts" => Ok(PlayerKind::Mcts),
+            "alphabeta" => Ok(PlayerKind::AlphaBeta),
+            _ => Err(CliError::InvalidValue {
+                option: String::from("player"),
+                value: String::from(s),
+            }),
+        }
+    }
+}
+
+/// The options of the `play` subcommand.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlayOptions {
+    pub x: PlayerKind,
+    pub o: PlayerKind,
+    pub level: Difficulty,
+    pub games: u32,
+    pub quiet: bool,
+    pub seed: Option<u64>,
+    /// Whether completing a line loses, rather than wins.
+    pub misere: bool,
+}
+
+impl Default for PlayOptions {
+    fn default() -> PlayOptions {
+        PlayOptions {
+            x: PlayerKind::Human,
+            o: PlayerKind::Optimal,
+            level: Difficulty::Medium,
+            games: 1,
+            quiet: false,
+            seed: None,
+            misere: false,
+        }
+    }
+}
+
+/// The options of the `simulate` subcommand.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimulateOptions {
+    pub x: PlayerKind,
+    pub o: PlayerKind,
+    pub level: Difficulty,
+    pub games: u64,
+    pub seed: u64,
+    pub threads: Option<usize>,
+}
+
+impl Default for SimulateOptions {
+    fn default() -> SimulateOptions {
+        SimulateOptions {
+            x: PlayerKind::Random,
+            o: PlayerKind::Random,
+            level: Difficulty::Medium,
+            games: 10000,
+            seed: 0,
+            threads: None,
+        }
+    }
+}
+
+/// The options of the `ultimate` subcommand.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UltimateOptions {
+    pub x: PlayerKind,
+    pub o: PlayerKind,
+    pub seed: Option<u64>,
+}
+
+impl Default for UltimateOptions {
+    fn default() -> UltimateOptions {
+        UltimateOptions {
+            x: PlayerKind::Human,
+            o: PlayerKind::Random,
+            seed: None,
+        }
+    }
+}
+
+/// What the binary has been asked to do.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    /// No command: ask the human whether to be X or O, and play the computer at the given level.
+    Interactive(Difficulty),
+    Play(PlayOptions),
+    Simulate(SimulateOptions),
+    Analyze(String),
+    Ultimate(UltimateOptions),
+    Help,
+}
+
+/// A problem with the command line.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CliError {
+    UnknownCommand(String),
+    UnknownOption(String),
+    MissingValue(String),
+    InvalidValue { option: String, value: String },
+    MissingBoard,
+    UnexpectedArgument(String),
+}
+
+impl std::fmt::Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CliError::UnknownCommand(command) => write!(f, "Unknown command '{}'", command),
+            CliError::UnknownOption(option) => write!(f, "Unknown option '{}'", option),
+            CliError::MissingValue(option) => write!(f, "Option '{}' needs a value", option),
+            CliError::InvalidValue { option, value } => {
+                write!(f, "Invalid value '{}' for {}", value, option)
+            }
+            CliError::MissingBoard => write!(f, "The analyze command needs a board"),
+            CliError::UnexpectedArgument(argument) => {
+                write!(f, "Unexpected argument '{}'", argument)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+/// Parses the command line arguments, not including the program name.
+pub fn parse_args(args: &[String]) -> Result<Command, CliError> {
+    let (command, rest) = match args.split_first() {
+        None => return Ok(Command::Interactive(Difficulty::Perfect)),
+        Some((command, rest)) => (command.as_str(), rest),
+    };
+    match command {
+        "play" => parse_play(rest).map(Command::Play),
+        "simulate" => parse_simulate(rest).map(Command::Simulate),
+        "analyze" => parse_analyze(rest),
+        "ultimate" => parse_ultimate(rest).map(Command::Ultimate),
+        "help" | "--help" | "-h" => Ok(Command::Help),
+        "--level" => parse_interactive(args),
+        _ => Err(CliError::UnknownCommand(String::from(command))),
+    }
+}
+
+fn parse_play(args: &[String]) -> Result<PlayOptions, CliError> {
+    let mut options = PlayOptions::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |option: &str| {
+            args.next()
+                .map(|value| value.as_str())
+                .ok_or_else(|| CliError::MissingValue(String::from(option)))
+        };
+        match arg.as_str() {
+            "--x" => options.x = parse_value(arg, value(arg)?)?,
+            "--o" => options.o = parse_value(arg, value(arg)?)?,
+            "--level" => options.level = parse_value(arg, value(arg)?)?,
+            "--games" => options.games = parse_value(arg, value(arg)?)?,
+            "--seed" => options.seed = Some(parse_value(arg, value(arg)?)?),
+            "--quiet" => options.quiet = true,
+            "--misere" => options.misere = true,
+            _ if arg.starts_with('-') => return Err(CliError::UnknownOption(arg.clone())),
+            _ => return Err(CliError::UnexpectedArgument(arg.clone())),
+        }
+    }
+    if options.games == 0 {
+        return Err(CliError::InvalidValue {
+            option: String::from("--games"),
+            value: String::from("0"),
+        });
+    }
+    Ok(options)
+}
+
+fn parse_simulate(args: &[String]) -> Result<SimulateOptions, CliError> {
+    let mut options = SimulateOptions::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |option: &str| {
+            args.next()
+                .map(|value| value.as_str())
+                .ok_or_else(|| CliError::MissingValue(String::from(option)))
+        };
+        match arg.as_str() {
+            "--x" => options.x = parse_computer_player(arg, value(arg)?)?,
+            "--o" => options.o = parse_computer_player(arg, value(arg)?)?,
+            "--level" => options.level = parse_value(arg, value(arg)?)?,
+            "--games" => options.games = parse_value(arg, value(arg)?)?,
+            "--seed" => options.seed = parse_value(arg, value(arg)?)?,
+            "--threads" => options.threads = Some(parse_value(arg, value(arg)?)?),
+            _ if arg.starts_with('-') => return Err(CliError::UnknownOption(arg.clone())),
+            _ => return Err(CliError::UnexpectedArgument(arg.clone())),
+        }
+    }
+    if options.threads == Some(0) {
+        return Err(CliError::InvalidValue {
+            option: String::from("--threads"),
+            value: String::from("0"),
+        });
+    }
+    Ok(options)
+}
+
+/// Parses the options of the `ultimate` subcommand.
+fn parse_ultimate(args: &[String]) -> Result<UltimateOptions, CliError> {
+    let mut options = UltimateOptions::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |option: &str| {
+            args.next()
+                .map(|value| value.as_str())
+                .ok_or_else(|| CliError::MissingValue(String::from(option)))
+        };
+        match arg.as_str() {
+            "--x" => options.x = parse_ultimate_player(arg, value(arg)?)?,
+            "--o" => options.o = parse_ultimate_player(arg, value(arg)?)?,
+            "--seed" => options.seed = Some(parse_value(arg, value(arg)?)?),
+            _ if arg.starts_with('-') => return Err(CliError::UnknownOption(arg.clone())),
+            _ => return Err(CliError::UnexpectedArgument(arg.clone())),
+        }
+    }
+    Ok(options)
+}
+
+/// Only humans and random players know how to play ultimate tic-tac-toe.
+fn parse_ultimate_player(option: &str, value: &str) -> Result<PlayerKind, CliError> {
+    match parse_value(option, value)? {
+        kind @ PlayerKind::Human | kind @ PlayerKind::Random => Ok(kind),
+        _ => Err(CliError::InvalidValue {
+            option: String::from(option),
+            value: String::from(value),
+        }),
+    }
+}
+
+/// Parses a player that can play without a human at the keyboard.
+fn parse_computer_player(option: &str, value: &str) -> Result<PlayerKind, CliError> {
+    match parse_value(option, value)? {
+        PlayerKind::Human => Err(CliError::InvalidValue {
+            option: String::from(option),
+            value: String::from(value),
+        }),
+        kind => Ok(kind),
+    }
+}
+
+fn parse_interactive(args: &[String]) -> Result<Command, CliError> {
+    match args {
+        [_option] => Err(CliError::MissingValue(String::from("--level"))),
+        [option, level] => Ok(Command::Interactive(parse_value(option, level)?)),
+        [_, _, extra, ..] => Err(CliError::UnexpectedArgument(extra.clone())),
+        [] => unreachable!(),
+    }
+}
+
+fn parse_analyze(args: &[String]) -> Result<Command, CliError> {
+    match args {
+        [] => Err(CliError::MissingBoard),
+        [board] => Ok(Command::Analyze(board.clone())),
+        [_, extra, ..] => Err(CliError::UnexpectedArgument(extra.clone())),
+    }
+}
+
+fn parse_value<T: FromStr>(option: &str, value: &str) -> Result<T, CliError> {
+    value.parse().map_err(|_| CliError::InvalidValue {
+        option: String::from(option),
+        value: String::from(value),
+    })
+}
+
+#[cfg(test)]
+mod cli_tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<Command, CliError> {
+        let args: Vec<String> = line.split_whitespace().map(String::from).collect();
+        parse_args(&args)
+    }
+
+    #[test]
+    fn test_no_arguments_is_interactive() {
+        assert_eq!(parse(""), Ok(Command::Interactive(Difficulty::Perfect)));
+    }
+
+    #[test]
+    fn test_interactive_level() {
+        assert_eq!(
+            parse("--level easy"),
+            Ok(Command::Interactive(Difficulty::Easy))
+        );
+        assert_eq!(
+            parse("--level"),
+            Err(CliError::MissingValue(String::from("--level")))
+        );
+        assert_eq!(
+            parse("--level silly"),
+            Err(CliError::InvalidValue {
+                option: String::from("--level"),
+                value: String::from("silly"),
+            })
+        );
+        assert_eq!(
+            parse("--level easy now"),
+            Err(CliError::UnexpectedArgument(String::from("now")))
+        );
+    }
+
+    #[test]
+    fn test_play_defaults() {
+        assert_eq!(parse("play"), Ok(Command::Play(PlayOptions::default())));
+    }
+
+    #[test]
+    fn test_play_options() {
+        assert_eq!(
+            parse(
+                "play --x random --o computer --level hard --games 1000 --quiet --seed 42 --misere"
+            ),
+            Ok(Command::Play(PlayOptions {
+                x: PlayerKind::Random,
+                o: PlayerKind::Computer,
+                level: Difficulty::Hard,
+                games: 1000,
+                quiet: true,
+                seed: Some(42),
+                misere: true,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_player_kinds() {
+        for (name, kind) in &[
+            ("human", PlayerKind::Human),
+            ("computer", PlayerKind::Computer),
+            ("optimal", PlayerKind::Optimal),
+            ("random", PlayerKind::Random),
+            ("center", PlayerKind::Center),
+            ("corners", PlayerKind::Corners),
+            ("heuristic", PlayerKind::Heuristic),
+            ("mcts", PlayerKind::Mcts),
+            ("alphabeta", PlayerKind::AlphaBeta),
+        ] {
+            assert_eq!(name.parse(), Ok(*kind));
+        }
+    }
+
+    #[test]
+    fn test_play_errors() {
+        assert_eq!(
+            parse("play --x robot"),
+            Err(CliError::InvalidValue {
+                option: String::from("--x"),
+                value: String::from("robot"),
+            })
+        );
+        assert_eq!(
+            parse("play --games"),
+            Err(CliError::MissingValue(String::from("--games")))
+        );
+        assert_eq!(
+            parse("play --games 0"),
+            Err(CliError::InvalidValue {
+                option: String::from("--games"),
+                value: String::from("0"),
+            })
+        );
+        assert_eq!(
+            parse("play --fast"),
+            Err(CliError::UnknownOption(String::from("--fast")))
+        );
+        assert_eq!(
+            parse("play now"),
+            Err(CliError::UnexpectedArgument(String::from("now")))
+        );
+    }
+
+    #[test]
+    fn test_simulate() {
+        assert_eq!(
+            parse("simulate"),
+            Ok(Command::Simulate(SimulateOptions::default()))
+        );
+        assert_eq!(
+            parse("simulate --x optimal --o corners --games 1000000 --seed 9 --threads 4"),
+            Ok(Command::Simulate(SimulateOptions {
+                x: PlayerKind::Optimal,
+                o: PlayerKind::Corners,
+                games: 1_000_000,
+                seed: 9,
+                threads: Some(4),
+                ..SimulateOptions::default()
+            }))
+        );
+        assert_eq!(
+            parse("simulate --o human"),
+            Err(CliError::InvalidValue {
+                option: String::from("--o"),
+                value: String::from("human"),
+            })
+        );
+        assert_eq!(
+            parse("simulate --threads 0"),
+            Err(CliError::InvalidValue {
+                option: String::from("--threads"),
+                value: String::from("0"),
+            })
+        );
+        assert_eq!(
+            parse("simulate --quiet"),
+            Err(CliError::UnknownOption(String::from("--quiet")))
+        );
+    }
+
+    #[test]
+    fn test_analyze() {
+        assert_eq!(
+            parse("analyze XO-OO-XX-"),
+            Ok(Command::Analyze(String::from("XO-OO-XX-")))
+        );
+        assert_eq!(parse("analyze"), Err(CliError::MissingBoard));
+        assert_eq!(
+            parse("analyze XO-OO-XX- extra"),
+            Err(CliError::UnexpectedArgument(String::from("extra")))
+        );
+    }
+
+    #[test]
+    fn test_ultimate() {
+        assert_eq!(
+            parse("ultimate"),
+            Ok(Command::Ultimate(UltimateOptions::default()))
+        );
+        assert_eq!(
+            parse("ultimate --x random --o human --seed 5"),
+            Ok(Command::Ultimate(UltimateOptions {
+                x: PlayerKind::Random,
+                o: PlayerKind::Human,
+                seed: Some(5),
+            }))
+        );
+        assert_eq!(
+            parse("ultimate --o optimal"),
+            Err(CliError::InvalidValue {
+                option: String::from("--o"),
+                value: String::from("optimal")
+            })
+        );
+        assert_eq!(
+            parse("ultimate --games 3"),
+            Err(CliError::UnknownOption(String::from("--games")))
+        );
+    }
+
+    #[test]
+    fn test_help_and_unknown_commands() {
+        assert_eq!(parse("help"), Ok(Command::Help));
+        assert_eq!(parse("--help"), Ok(Command::Help));
+        assert_eq!(
+            parse("train"),
+            Err(CliError::UnknownCommand(String::from("train")))
+        );
+        assert_eq!(
+            CliError::MissingValue(String::from("--seed")).to_string(),
+            "Option '--seed' needs a value"
+        );
+    }
+} // mod cli_tests
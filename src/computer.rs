@@ -0,0 +1,261 @@
+//! A computer player whose strength can be adjusted, for opponents that can be beaten.
+use crate::board::{Board, Position};
+use crate::optimal::OptimalPlayer;
+use crate::player::Player;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::str::FromStr;
+
+/// How well a ComputerPlayer plays.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+    /// Never makes a mistake, so can never be beaten.
+    Perfect,
+}
+
+impl Difficulty {
+    /// All of the difficulties, from easiest to hardest.
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Medium,
+        Difficulty::Hard,
+        Difficulty::Perfect,
+    ];
+
+    /// Returns the chance, between 0 and 1, of a deliberate mistake on each turn.
+    pub fn mistake_rate(self) -> f64 {
+        match self {
+            Difficulty::Easy => 0.5,
+            Difficulty::Medium => 0.25,
+            Difficulty::Hard => 0.1,
+            Difficulty::Perfect => 0.0,
+        }
+    }
+}
+
+impl std::fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+            Difficulty::Perfect => "perfect",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = ParseDifficultyError;
+
+    /// Parses the lowercase name of a difficulty, as written by Display.
+    ///
+    /// # Examples
+    /// ```
+    /// use tictactoelib::computer::Difficulty;
+    ///
+    /// assert_eq!("hard".parse(), Ok(Difficulty::Hard));
+    /// assert!("impossible".parse::<Difficulty>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Difficulty, ParseDifficultyError> {
+        Difficulty::ALL
+            .iter()
+            .copied()
+            .find(|difficulty| difficulty.to_string() == s)
+            .ok_or_else(|| ParseDifficultyError(String::from(s)))
+    }
+}
+
+/// The error returned when parsing an unknown difficulty.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseDifficultyError(String);
+
+impl std::fmt::Display for ParseDifficultyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Unknown difficulty '{}'", self.0)
+    }
+}
+
+impl std::error::Error for ParseDifficultyError {}
+
+/// A player that plays like the OptimalPlayer, except for deliberate mistakes.
+///
+/// On each turn, with a chance given by the mistake rate, it picks one of the moves
+/// that is worse than the best move.  When every move is equally good, there is no
+/// mistake to make, and it plays as well as it can.
+pub struct ComputerPlayer {
+    optimal: OptimalPlayer,
+    mistake_rate: f64,
+    rng: StdRng,
+}
+
+impl ComputerPlayer {
+    /// Creates a player of the given difficulty, seeded from the operating system's entropy source.
+    pub fn new(difficulty: Difficulty) -> ComputerPlayer {
+        ComputerPlayer::from_rng(difficulty, StdRng::from_entropy())
+    }
+
+    /// Creates a player of the given difficulty, whose mistakes are reproducible for a given seed.
+    ///
+    /// # Examples
+    /// ```
+    /// use tictactoelib::board::Board;
+    /// use tictactoelib::computer::{ComputerPlayer, Difficulty};
+    /// use tictactoelib::player::Player;
+    ///
+    /// let board = Board::new();
+    /// let first = ComputerPlayer::with_seed(Difficulty::Easy, 3).take_turn(&board);
+    /// let second = ComputerPlayer::with_seed(Difficulty::Easy, 3).take_turn(&board);
+    /// assert_eq!(first, second);
+    /// ```
+    pub fn with_seed(difficulty: Difficulty, seed: u64) -> ComputerPlayer {
+        ComputerPlayer::from_rng(difficulty, StdRng::seed_from_u64(seed))
+    }
+
+    fn from_rng(difficulty: Difficulty, rng: StdRng) -> ComputerPlayer {
+        ComputerPlayer {
+            optimal: OptimalPlayer::new(),
+            mistake_rate: difficulty.mistake_rate(),
+            rng,
+        }
+    }
+
+    /// Returns the chance, between 0 and 1, of a deliberate mistake on each turn.
+    pub fn mistake_rate(&self) -> f64 {
+        self.mistake_rate
+    }
+
+    /// Changes the chance of a deliberate mistake on each turn, for finer control than a Difficulty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rate is not between 0 and 1.
+    pub fn set_mistake_rate(&mut self, mistake_rate: f64) {
+        if !(0.0..=1.0).contains(&mistake_rate) {
+            panic!("Invalid mistake rate: {}", mistake_rate);
+        }
+        self.mistake_rate = mistake_rate;
+    }
+}
+
+impl Player for ComputerPlayer {
+    fn take_turn(&mut self, board: &Board) -> Position {
+        let evaluations = self.optimal.evaluate_moves(board);
+        let best_score = evaluations
+            .iter()
+            .map(|(_position, evaluation)| evaluation.score())
+            .max()
+            .expect("No moves left!");
+
+        let mistakes: Vec<Position> = evaluations
+            .iter()
+            .filter(|(_position, evaluation)| evaluation.score() < best_score)
+            .map(|(position, _evaluation)| *position)
+            .collect();
+        if !mistakes.is_empty() && self.rng.gen_bool(self.mistake_rate) {
+            return mistakes[self.rng.gen_range(0, mistakes.len())];
+        }
+
+        evaluations
+            .iter()
+            .find(|(_position, evaluation)| evaluation.score() == best_score)
+            .map(|(position, _evaluation)| *position)
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod computer_tests {
+    use super::*;
+    use crate::board::{GameResult, Token};
+    use crate::game::Game;
+    use crate::strategies::RandomPlayer;
+
+    #[test]
+    fn test_difficulty_names() {
+        for difficulty in Difficulty::ALL.iter() {
+            assert_eq!(difficulty.to_string().parse(), Ok(*difficulty));
+        }
+        assert_eq!(
+            "expert".parse::<Difficulty>(),
+            Err(ParseDifficultyError(String::from("expert")))
+        );
+        assert_eq!(
+            ParseDifficultyError(String::from("expert")).to_string(),
+            "Unknown difficulty 'expert'"
+        );
+    }
+
+    #[test]
+    fn test_easier_levels_make_more_mistakes() {
+        for pair in Difficulty::ALL.windows(2) {
+            assert_gt!(pair[0].mistake_rate(), pair[1].mistake_rate());
+        }
+        assert_eq!(Difficulty::Perfect.mistake_rate(), 0.0);
+    }
+
+    #[test]
+    fn test_perfect_never_loses() {
+        let mut computer = ComputerPlayer::with_seed(Difficulty::Perfect, 1);
+        for seed in 0..20 {
+            let game = Game::new(&mut computer, RandomPlayer::with_seed(seed));
+            assert_ne!(game.result(), GameResult::Win(Token::O));
+            let game = Game::new(RandomPlayer::with_seed(seed), &mut computer);
+            assert_ne!(game.result(), GameResult::Win(Token::X));
+        }
+    }
+
+    #[test]
+    fn test_always_mistaken() {
+        let mut computer = ComputerPlayer::with_seed(Difficulty::Perfect, 1);
+        computer.set_mistake_rate(1.0);
+        // Only c1 stops O from winning at once.
+        let board = Board::from_string(
+            "OO-\
+             X--\
+             X--",
+        );
+        assert_ne!(computer.take_turn(&board), Position::new(0, 2));
+
+        // With only one move left, there is no mistake to make.
+        let board = Board::from_string(
+            "XOX\
+             OO-\
+             XXO",
+        );
+        assert_eq!(computer.take_turn(&board), Position::new(1, 2));
+    }
+
+    #[test]
+    fn test_easy_can_be_beaten() {
+        let mut losses = 0;
+        for seed in 0..20 {
+            let computer = ComputerPlayer::with_seed(Difficulty::Easy, seed);
+            let game = Game::new(RandomPlayer::with_seed(seed), computer);
+            if game.result() == GameResult::Win(Token::X) {
+                losses += 1;
+            }
+        }
+        assert_gt!(losses, 0);
+    }
+
+    #[test]
+    fn test_seeded_games_repeat() {
+        let play = |seed| {
+            let x = ComputerPlayer::with_seed(Difficulty::Medium, seed);
+            let o = ComputerPlayer::with_seed(Difficulty::Medium, seed + 1);
+            *Game::new(x, o).board()
+        };
+        assert_eq!(play(5).moves(), play(5).moves());
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid mistake rate: 1.5")]
+    fn test_invalid_mistake_rate() {
+        ComputerPlayer::new(Difficulty::Easy).set_mistake_rate(1.5);
+    }
+} // mod computer_tests
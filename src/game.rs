@@ -1,29 +1,113 @@
-use crate::board::{Board, GameResult, Token};
+//! Plays games between two players, one move at a time.
+use crate::board::{Board, GameResult, Position, Token};
 use crate::player::Player;
 
+/// Receives notifications as a game is played.
+///
+/// All of the methods do nothing by default, so implementors only need the ones they care about.
+pub trait GameObserver {
+    /// Called before a player is asked for their move.
+    fn on_turn_start(&mut self, _board: &Board, _player: Token) {}
+
+    /// Called after a player's move has been added to the board.
+    fn on_move(&mut self, _board: &Board, _player: Token, _at: Position) {}
+
+    /// Called once, after the move that ended the game.
+    fn on_game_over(&mut self, _board: &Board, _result: &GameResult) {}
+}
+
+/// Prints the progress of a game to stdout.
+pub struct ConsoleObserver {}
+
+impl GameObserver for ConsoleObserver {
+    fn on_turn_start(&mut self, _board: &Board, player: Token) {
+        println!("It is {}'s turn", player);
+    }
+
+    fn on_move(&mut self, board: &Board, _player: Token, _at: Position) {
+        println!("{}", board);
+    }
+}
+
 /// Game represents a single game played between two players
-pub struct Game {
+pub struct Game<X: Player, O: Player> {
     board: Board,
+    x: X,
+    o: O,
+    observers: Vec<Box<dyn GameObserver>>,
 }
 
-impl Game {
+impl<X: Player, O: Player> Game<X, O> {
     /// Creates and plays a game between two players, given their strategies.
-    pub fn new(mut x: impl Player, mut o: impl Player) -> Game {
-        let mut board = Board::new();
-
-        while board.get_game_result() == GameResult::InProgress {
-            let whose_turn = board.whose_turn().unwrap();
-            println!("It is {}'s turn", whose_turn);
-            let their_move = if whose_turn == Token::X {
-                x.take_turn(&board)
-            } else {
-                o.take_turn(&board)
-            };
-            board.add_move(whose_turn, their_move);
-            println!("{}", board);
+    ///
+    /// Nothing is printed; use start() and add an observer to follow the game.
+    pub fn new(x: X, o: O) -> Game<X, O> {
+        let mut game = Game::start(x, o);
+        game.play_to_end();
+        game
+    }
+
+    /// Creates a game between two players on a standard board, without playing any moves.
+    pub fn start(x: X, o: O) -> Game<X, O> {
+        Game::start_from(Board::new(), x, o)
+    }
+
+    /// Creates a game between two players, continuing from the given board.
+    pub fn start_from(board: Board, x: X, o: O) -> Game<X, O> {
+        Game {
+            board,
+            x,
+            o,
+            observers: Vec::new(),
         }
+    }
 
-        Game { board }
+    /// Adds an observer, to be notified of every move from now on.
+    pub fn add_observer(&mut self, observer: impl GameObserver + 'static) {
+        self.observers.push(Box::new(observer));
+    }
+
+    /// Asks the player whose turn it is for a move, and plays it.
+    ///
+    /// Returns the position played, or None if the game was already over.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the player chooses a move that is not allowed.
+    pub fn step(&mut self) -> Option<Position> {
+        let whose_turn = self.board.whose_turn()?;
+        for observer in self.observers.iter_mut() {
+            observer.on_turn_start(&self.board, whose_turn);
+        }
+
+        let their_move = if whose_turn == Token::X {
+            self.x.take_turn(&self.board)
+        } else {
+            self.o.take_turn(&self.board)
+        };
+        self.board.add_move(whose_turn, their_move);
+        for observer in self.observers.iter_mut() {
+            observer.on_move(&self.board, whose_turn, their_move);
+        }
+
+        let result = self.board.get_game_result();
+        if result != GameResult::InProgress {
+            for observer in self.observers.iter_mut() {
+                observer.on_game_over(&self.board, &result);
+            }
+        }
+        Some(their_move)
+    }
+
+    /// Plays moves until the game is over, and returns the result.
+    pub fn play_to_end(&mut self) -> GameResult {
+        while self.step().is_some() {}
+        self.result()
+    }
+
+    /// Returns the board, as it stands.
+    pub fn board(&self) -> &Board {
+        &self.board
     }
 
     /// Returns the result of the game
@@ -48,10 +132,10 @@ mod tests {
             "Simulating {} games between players who play randomly.",
             GAME_COUNT
         );
+        let mut x = RandomPlayer::with_seed(1);
+        let mut o = RandomPlayer::with_seed(2);
         for _i in 1..GAME_COUNT + 1 {
-            let x = RandomPlayer {};
-            let o = RandomPlayer {};
-            let game = Game::new(x, o);
+            let game = Game::new(&mut x, &mut o);
             match game.result() {
                 GameResult::Draw => draw_count += 1,
                 GameResult::Win(Token::X) => x_win_count += 1,
@@ -85,3 +169,118 @@ mod tests {
         //panic!("Uncomment me to check the output");
     }
 }
+
+#[cfg(test)]
+mod engine_tests {
+    use super::*;
+    use crate::optimal::OptimalPlayer;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// Plays the given positions, in order.
+    struct ScriptedPlayer {
+        moves: Vec<Position>,
+    }
+
+    impl Player for ScriptedPlayer {
+        fn take_turn(&mut self, _board: &Board) -> Position {
+            self.moves.remove(0)
+        }
+    }
+
+    /// Records every notification as a line of text.
+    struct RecordingObserver {
+        events: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl GameObserver for RecordingObserver {
+        fn on_turn_start(&mut self, _board: &Board, player: Token) {
+            self.events.borrow_mut().push(format!("turn {}", player));
+        }
+
+        fn on_move(&mut self, _board: &Board, player: Token, at: Position) {
+            self.events
+                .borrow_mut()
+                .push(format!("move {} {},{}", player, at.row(), at.column()));
+        }
+
+        fn on_game_over(&mut self, _board: &Board, result: &GameResult) {
+            self.events.borrow_mut().push(format!("over {:?}", result));
+        }
+    }
+
+    #[test]
+    fn test_start_plays_nothing() {
+        let game = Game::start(OptimalPlayer::new(), OptimalPlayer::new());
+        assert_eq!(*game.board(), Board::new());
+        assert_eq!(game.result(), GameResult::InProgress);
+    }
+
+    #[test]
+    fn test_step() {
+        let x = ScriptedPlayer {
+            moves: vec![
+                Position::new(0, 0),
+                Position::new(0, 1),
+                Position::new(0, 2),
+            ],
+        };
+        let o = ScriptedPlayer {
+            moves: vec![Position::new(1, 0), Position::new(1, 1)],
+        };
+        let mut game = Game::start(x, o);
+        assert_eq!(game.step(), Some(Position::new(0, 0)));
+        assert_eq!(game.step(), Some(Position::new(1, 0)));
+        assert_eq!(game.board().moves().len(), 2);
+        assert_eq!(game.play_to_end(), GameResult::Win(Token::X));
+        assert_eq!(game.step(), None);
+    }
+
+    #[test]
+    fn test_observer_notifications() {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let x = ScriptedPlayer {
+            moves: vec![
+                Position::new(0, 0),
+                Position::new(0, 1),
+                Position::new(0, 2),
+            ],
+        };
+        let o = ScriptedPlayer {
+            moves: vec![Position::new(1, 0), Position::new(1, 1)],
+        };
+        let mut game = Game::start(x, o);
+        game.add_observer(RecordingObserver {
+            events: events.clone(),
+        });
+        game.play_to_end();
+        assert_eq!(
+            *events.borrow(),
+            vec![
+                "turn X",
+                "move X 0,0",
+                "turn O",
+                "move O 1,0",
+                "turn X",
+                "move X 0,1",
+                "turn O",
+                "move O 1,1",
+                "turn X",
+                "move X 0,2",
+                "over Win(X)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_start_from_board() {
+        let board = Board::from_string(
+            "XO-\
+             XO-\
+             ---",
+        );
+        let mut game = Game::start_from(board, OptimalPlayer::new(), OptimalPlayer::new());
+        assert_eq!(game.play_to_end(), GameResult::Win(Token::X));
+        assert_eq!(game.board().last_move(), Some(Position::new(2, 0)));
+    }
+} // mod engine_tests
@@ -0,0 +1,290 @@
+//! A rule-based player, following the strategy of Newell and Simon's 1972 tic-tac-toe program.
+//!
+//! Rather than searching the game tree, it plays like a textbook human:
+//! it takes the first move suggested by an ordered list of simple rules.
+use crate::board::{Board, Position, Token};
+use crate::player::Player;
+
+/// One rule of thumb for choosing a move.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Rule {
+    /// Complete a line of one's own.
+    Win,
+    /// Take the square where the opponent would complete a line.
+    Block,
+    /// Make two threats at once, so that only one can be blocked.
+    Fork,
+    /// Stop the opponent from making a fork.
+    BlockFork,
+    /// Take the center square.
+    Center,
+    /// Take the corner opposite one of the opponent's corners.
+    OppositeCorner,
+    /// Take any corner.
+    EmptyCorner,
+    /// Take a square on an edge, between the corners.
+    EmptySide,
+}
+
+impl Rule {
+    /// All of the rules, in the order of Newell and Simon's strategy.
+    pub const ALL: [Rule; 8] = [
+        Rule::Win,
+        Rule::Block,
+        Rule::Fork,
+        Rule::BlockFork,
+        Rule::Center,
+        Rule::OppositeCorner,
+        Rule::EmptyCorner,
+        Rule::EmptySide,
+    ];
+
+    /// Returns the positions where the rule suggests the token should move, in board order.
+    ///
+    /// # Examples
+    /// ```
+    /// use tictactoelib::board::{Board, Position, Token};
+    /// use tictactoelib::heuristic::Rule;
+    ///
+    /// let board: Board = "O--/-X-/---".parse().unwrap();
+    /// assert_eq!(Rule::OppositeCorner.moves(&board, Token::X), vec![Position::new(2, 2)]);
+    /// assert!(Rule::Win.moves(&board, Token::X).is_empty());
+    /// ```
+    pub fn moves(self, board: &Board, token: Token) -> Vec<Position> {
+        let opponent = token.opponent();
+        match self {
+            Rule::Win => board.winning_moves(token),
+            Rule::Block => board.must_block(token),
+            Rule::Fork => board.fork_moves(token),
+            Rule::BlockFork => board.fork_blocking_moves(token),
+            Rule::Center => board
+                .empty_positions_iter()
+                .filter(|position| is_center(*position, board))
+                .collect(),
+            Rule::OppositeCorner => board
+                .empty_positions_iter()
+                .filter(|position| {
+                    is_corner(*position, board)
+                        && board.token_at(opposite(*position, board)) == Some(opponent)
+                })
+                .collect(),
+            Rule::EmptyCorner => board
+                .empty_positions_iter()
+                .filter(|position| is_corner(*position, board))
+                .collect(),
+            Rule::EmptySide => board
+                .empty_positions_iter()
+                .filter(|position| is_edge(*position, board) && !is_corner(*position, board))
+                .collect(),
+        }
+    }
+}
+
+impl std::fmt::Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            Rule::Win => "win",
+            Rule::Block => "block",
+            Rule::Fork => "fork",
+            Rule::BlockFork => "block fork",
+            Rule::Center => "center",
+            Rule::OppositeCorner => "opposite corner",
+            Rule::EmptyCorner => "empty corner",
+            Rule::EmptySide => "empty side",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+fn is_center(position: Position, board: &Board) -> bool {
+    let is_middle = |value: u8, size: u8| value == (size - 1) / 2 || value == size / 2;
+    is_middle(position.row(), board.rows()) && is_middle(position.column(), board.columns())
+}
+
+fn is_edge(position: Position, board: &Board) -> bool {
+    position.row() == 0
+        || position.row() == board.rows() - 1
+        || position.column() == 0
+        || position.column() == board.columns() - 1
+}
+
+fn is_corner(position: Position, board: &Board) -> bool {
+    (position.row() == 0 || position.row() == board.rows() - 1)
+        && (position.column() == 0 || position.column() == board.columns() - 1)
+}
+
+fn opposite(position: Position, board: &Board) -> Position {
+    Position::new(
+        board.rows() - 1 - position.row(),
+        board.columns() - 1 - position.column(),
+    )
+}
+
+/// A player that moves by the first of its rules that suggests a move.
+///
+/// If none of its rules applies, it takes the first empty position.
+/// The rules are for standard scoring, and make poor moves under misère rules.
+///
+/// # Examples
+/// ```
+/// use tictactoelib::board::{Board, Position};
+/// use tictactoelib::heuristic::{HeuristicPlayer, Rule};
+/// use tictactoelib::player::Player;
+///
+/// let mut player = HeuristicPlayer::new();
+/// let board: Board = "XX-/OO-/---".parse().unwrap();
+/// assert_eq!(player.take_turn(&board), Position::new(0, 2));
+/// assert_eq!(player.last_rule(), Some(Rule::Win));
+/// ```
+pub struct HeuristicPlayer {
+    rules: Vec<Rule>,
+    last_rule: Option<Rule>,
+}
+
+impl HeuristicPlayer {
+    /// Creates a player that follows all of the rules, in Newell and Simon's order.
+    pub fn new() -> HeuristicPlayer {
+        HeuristicPlayer::with_rules(&Rule::ALL)
+    }
+
+    /// Creates a player that follows only the given rules, in the given order.
+    ///
+    /// # Examples
+    /// ```
+    /// use tictactoelib::board::{Board, Position};
+    /// use tictactoelib::heuristic::{HeuristicPlayer, Rule};
+    /// use tictactoelib::player::Player;
+    ///
+    /// // A player that would rather take a corner than win.
+    /// let mut player = HeuristicPlayer::with_rules(&[Rule::EmptyCorner, Rule::Win]);
+    /// let board: Board = "XX-/OO-/---".parse().unwrap();
+    /// assert_eq!(player.take_turn(&board), Position::new(0, 2));
+    /// assert_eq!(player.last_rule(), Some(Rule::EmptyCorner));
+    /// ```
+    pub fn with_rules(rules: &[Rule]) -> HeuristicPlayer {
+        HeuristicPlayer {
+            rules: rules.to_vec(),
+            last_rule: None,
+        }
+    }
+
+    /// Returns the rules the player follows, in order.
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// Returns the rule that chose the player's last move,
+    /// or None if it has not moved, or no rule applied.
+    pub fn last_rule(&self) -> Option<Rule> {
+        self.last_rule
+    }
+
+    /// Returns the move the player would make, and the rule that chose it,
+    /// or None if none of the rules applies.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the game is over.
+    pub fn choose(&self, board: &Board) -> Option<(Position, Rule)> {
+        let token = board.whose_turn().expect("Game should not be over!");
+        self.rules.iter().find_map(|rule| {
+            rule.moves(board, token)
+                .first()
+                .map(|position| (*position, *rule))
+        })
+    }
+}
+
+impl Default for HeuristicPlayer {
+    fn default() -> HeuristicPlayer {
+        HeuristicPlayer::new()
+    }
+}
+
+impl Player for HeuristicPlayer {
+    fn take_turn(&mut self, board: &Board) -> Position {
+        match self.choose(board) {
+            Some((position, rule)) => {
+                self.last_rule = Some(rule);
+                position
+            }
+            None => {
+                self.last_rule = None;
+                board.empty_positions()[0]
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod heuristic_tests {
+    use super::*;
+    use crate::verification;
+
+    #[test]
+    fn test_never_loses() {
+        let verification = verification::verify(HeuristicPlayer::new());
+        assert!(verification.never_loses(), "{}", verification);
+    }
+
+    #[test]
+    fn test_loses_without_blocking_forks() {
+        let rules: Vec<Rule> = Rule::ALL
+            .iter()
+            .copied()
+            .filter(|rule| *rule != Rule::BlockFork)
+            .collect();
+        let verification = verification::verify(HeuristicPlayer::with_rules(&rules));
+        assert!(!verification.never_loses());
+    }
+
+    #[test]
+    fn test_reports_rules() {
+        let mut player = HeuristicPlayer::new();
+        assert_eq!(player.last_rule(), None);
+
+        let cases = [
+            ("XX-/OO-/---", Position::new(0, 2), Rule::Win),
+            ("XX-/O--/---", Position::new(0, 2), Rule::Block),
+            ("XO-/-X-/--O", Position::new(1, 0), Rule::Fork),
+            ("X--/-O-/--X", Position::new(0, 1), Rule::BlockFork),
+            ("---/---/---", Position::new(1, 1), Rule::Center),
+            ("O--/-X-/---", Position::new(2, 2), Rule::OppositeCorner),
+            ("---/-X-/---", Position::new(0, 0), Rule::EmptyCorner),
+            ("OXO/-X-/XOX", Position::new(1, 0), Rule::EmptySide),
+        ];
+        for (board, position, rule) in &cases {
+            let board: Board = board.parse().unwrap();
+            assert_eq!(player.take_turn(&board), *position, "Board:\n{}", board);
+            assert_eq!(player.last_rule(), Some(*rule), "Board:\n{}", board);
+        }
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        let mut player = HeuristicPlayer::with_rules(&[Rule::Center]);
+        assert_eq!(player.rules(), &[Rule::Center]);
+        let board: Board = "---/-X-/---".parse().unwrap();
+        assert_eq!(player.choose(&board), None);
+        assert_eq!(player.take_turn(&board), Position::new(0, 0));
+        assert_eq!(player.last_rule(), None);
+    }
+
+    #[test]
+    fn test_rule_names() {
+        let names: Vec<String> = Rule::ALL.iter().map(|rule| rule.to_string()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "win",
+                "block",
+                "fork",
+                "block fork",
+                "center",
+                "opposite corner",
+                "empty corner",
+                "empty side"
+            ]
+        );
+    }
+} // mod heuristic_tests
@@ -0,0 +1,334 @@
+//! Advice for human players: the value of every move, and why.
+//!
+//! Squares are named by number, counting row by row from the top left
+//! (1-9 on the standard board), as the HumanPlayer asks for them.
+use crate::board::{Board, Direction, Line, Position, Rules, Token};
+use crate::optimal::{Evaluation, OptimalPlayer};
+
+/// The value of one move, with a short explanation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Advice {
+    position: Position,
+    square: usize,
+    evaluation: Evaluation,
+    explanation: String,
+}
+
+impl Advice {
+    /// Returns the position of the move.
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    /// Returns the number of the square, counting row by row from 1 at the top left.
+    pub fn square(&self) -> usize {
+        self.square
+    }
+
+    /// Returns whether the move wins, draws or loses with best play, and how soon.
+    pub fn evaluation(&self) -> Evaluation {
+        self.evaluation
+    }
+
+    /// Returns a short, human-readable reason for playing (or not playing) the move,
+    /// such as "blocks O's row 2" or "creates a fork on 1 and 9".
+    pub fn explanation(&self) -> &str {
+        &self.explanation
+    }
+}
+
+impl std::fmt::Display for Advice {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} - {}",
+            self.square, self.evaluation, self.explanation
+        )
+    }
+}
+
+/// Gives advice on every move, using an OptimalPlayer to value them.
+#[derive(Default)]
+pub struct Advisor {
+    optimal: OptimalPlayer,
+}
+
+impl Advisor {
+    /// Creates an advisor.
+    pub fn new() -> Advisor {
+        Advisor {
+            optimal: OptimalPlayer::new(),
+        }
+    }
+
+    /// Returns advice for every empty square, in board order,
+    /// for the player whose turn it is.
+    ///
+    /// # Examples
+    /// ```
+    /// use tictactoelib::board::Board;
+    /// use tictactoelib::hint::Advisor;
+    /// use tictactoelib::optimal::Evaluation;
+    ///
+    /// let board: Board = "XX-/OO-/---".parse().unwrap();
+    /// let advice = Advisor::new().advise(&board);
+    /// assert_eq!(advice[0].square(), 3);
+    /// assert_eq!(advice[0].evaluation(), Evaluation::Win(1));
+    /// assert_eq!(advice[0].explanation(), "wins with row 1");
+    /// assert_eq!(advice[1].explanation(), "blocks O's row 2");
+    /// ```
+    pub fn advise(&mut self, board: &Board) -> Vec<Advice> {
+        let player = match board.whose_turn() {
+            Some(player) => player,
+            None => return Vec::new(),
+        };
+        self.optimal
+            .evaluate_moves(board)
+            .into_iter()
+            .map(|(position, evaluation)| Advice {
+                position,
+                square: square_number(position, board),
+                evaluation,
+                explanation: explain(board, player, position),
+            })
+            .collect()
+    }
+
+    /// Returns the advice for the best move, or None if the game is over.
+    pub fn best(&mut self, board: &Board) -> Option<Advice> {
+        let mut best: Option<Advice> = None;
+        for advice in self.advise(board) {
+            if best.as_ref().map_or(true, |best| {
+                advice.evaluation.score() > best.evaluation.score()
+            }) {
+                best = Some(advice);
+            }
+        }
+        best
+    }
+}
+
+fn square_number(position: Position, board: &Board) -> usize {
+    position.row() as usize * board.columns() as usize + position.column() as usize + 1
+}
+
+/// Lists the squares by number, as in "1, 3 and 9".
+fn list_squares(squares: &[Position], board: &Board) -> String {
+    let numbers: Vec<String> = squares
+        .iter()
+        .map(|square| square_number(*square, board).to_string())
+        .collect();
+    match numbers.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} and {}", rest.join(", "), last),
+        _ => numbers.join(""),
+    }
+}
+
+/// Names a line, as in "row 2", "column 1" or "diagonal 3-7".
+fn describe(line: &Line, board: &Board) -> String {
+    match line.direction() {
+        Direction::Across => format!("row {}", line.start().row() + 1),
+        Direction::Down => format!("column {}", line.start().column() + 1),
+        Direction::Diagonal | Direction::AntiDiagonal => format!(
+            "diagonal {}-{}",
+            square_number(line.start(), board),
+            square_number(line.end(), board)
+        ),
+    }
+}
+
+/// Returns the squares, other than the one just played,
+/// where the token could not win before, but could after playing there.
+fn threats_after(board: &Board, played: Position, token: Token) -> Vec<Position> {
+    let existing = board.winning_moves(token);
+    board
+        .winning_moves_after(token, played)
+        .into_iter()
+        .filter(|square| *square != played && !existing.contains(square))
+        .collect()
+}
+
+fn explain(board: &Board, player: Token, position: Position) -> String {
+    if board.rules() == Rules::Misere {
+        return explain_misere(board, player, position);
+    }
+    let opponent = player.opponent();
+    if let Some(line) = board.winning_line(player, position) {
+        return format!("wins with {}", describe(&line, board));
+    }
+
+    let mut reasons = Vec::new();
+    if let Some(line) = board.winning_line(opponent, position) {
+        reasons.push(format!("blocks {}'s {}", opponent, describe(&line, board)));
+    }
+    let threats = threats_after(board, position, player);
+    if threats.len() >= 2 {
+        reasons.push(format!(
+            "creates a fork on {}",
+            list_squares(&threats, board)
+        ));
+    } else if threats.len() == 1 {
+        reasons.push(format!(
+            "threatens to win on {}",
+            list_squares(&threats, board)
+        ));
+    }
+    // Taking the opponent's fork square only stops the fork
+    // if none is left after the opponent's forced reply.
+    if threats_after(board, position, opponent).len() >= 2
+        && board.fork_blocking_moves(player).contains(&position)
+    {
+        reasons.push(format!("stops {}'s fork", opponent));
+    }
+
+    let opponent_wins: Vec<Position> = board
+        .must_block(player)
+        .into_iter()
+        .filter(|square| *square != position)
+        .collect();
+    if !opponent_wins.is_empty() {
+        reasons.push(format!(
+            "lets {} win on {}",
+            opponent,
+            list_squares(&opponent_wins, board)
+        ));
+    }
+
+    if reasons.is_empty() {
+        String::from("makes no immediate threat")
+    } else {
+        reasons.join("; ")
+    }
+}
+
+/// Under misère rules, threats and blocks mean the opposite, so only lines completed are named.
+fn explain_misere(board: &Board, player: Token, position: Position) -> String {
+    match board.winning_line(player, position) {
+        Some(line) => format!("loses by completing {}", describe(&line, board)),
+        None => String::from("does not complete a line"),
+    }
+}
+
+#[cfg(test)]
+mod hint_tests {
+    use super::*;
+
+    fn explanations(board: &Board) -> Vec<(usize, String)> {
+        Advisor::new()
+            .advise(board)
+            .into_iter()
+            .map(|advice| (advice.square(), String::from(advice.explanation())))
+            .collect()
+    }
+
+    #[test]
+    fn test_win_and_block() {
+        let board = Board::from_string(
+            "XX-\
+             OO-\
+             ---",
+        );
+        let explanations = explanations(&board);
+        assert_eq!(explanations[0], (3, String::from("wins with row 1")));
+        assert_eq!(explanations[1], (6, String::from("blocks O's row 2")));
+        assert_eq!(explanations[2], (7, String::from("lets O win on 6")));
+    }
+
+    #[test]
+    fn test_fork() {
+        let board = Board::from_string(
+            "X-O\
+             -O-\
+             --X",
+        );
+        let advice = Advisor::new().advise(&board);
+        let seven = advice.iter().find(|advice| advice.square() == 7).unwrap();
+        assert_eq!(
+            seven.explanation(),
+            "blocks O's diagonal 3-7; creates a fork on 4 and 8"
+        );
+        assert_eq!(seven.evaluation(), Evaluation::Win(3));
+        assert_eq!(
+            seven.to_string(),
+            "7: win in 3 moves - blocks O's diagonal 3-7; creates a fork on 4 and 8"
+        );
+    }
+
+    #[test]
+    fn test_fork_block() {
+        // X threatens to fork on 3 or 7; O must not take a corner, which forces X onto a fork.
+        let board = Board::from_string(
+            "X--\
+             -O-\
+             --X",
+        );
+        let advice = Advisor::new().advise(&board);
+        let three = advice.iter().find(|advice| advice.square() == 3).unwrap();
+        assert_eq!(three.explanation(), "threatens to win on 7");
+        assert_eq!(three.evaluation(), Evaluation::Loss(4));
+        let two = advice.iter().find(|advice| advice.square() == 2).unwrap();
+        assert_eq!(two.explanation(), "threatens to win on 8");
+        assert_eq!(two.evaluation(), Evaluation::Draw(6));
+
+        // Here X's only fork is on 3, and taking it leaves X none after blocking on 7.
+        let board = Board::from_string(
+            "X--\
+             -OX\
+             ---",
+        );
+        let advice = Advisor::new().advise(&board);
+        let three = advice.iter().find(|advice| advice.square() == 3).unwrap();
+        assert_eq!(three.explanation(), "threatens to win on 7; stops X's fork");
+        assert_eq!(three.evaluation(), Evaluation::Draw(6));
+    }
+
+    #[test]
+    fn test_quiet_moves() {
+        let explanations = explanations(&Board::new());
+        assert_eq!(explanations.len(), 9);
+        for (_square, explanation) in explanations {
+            assert_eq!(explanation, "makes no immediate threat");
+        }
+    }
+
+    #[test]
+    fn test_best() {
+        let mut advisor = Advisor::new();
+        let board = Board::from_string(
+            "XX-\
+             OO-\
+             ---",
+        );
+        assert_eq!(advisor.best(&board).unwrap().square(), 3);
+        let finished = Board::from_string(
+            "XXX\
+             OO-\
+             ---",
+        );
+        assert_eq!(advisor.best(&finished), None);
+        assert!(advisor.advise(&finished).is_empty());
+    }
+
+    #[test]
+    fn test_misere() {
+        let mut board = Board::from_string(
+            "XX-\
+             OO-\
+             ---",
+        );
+        board.set_rules(Rules::Misere);
+        let advice = Advisor::new().advise(&board);
+        assert_eq!(advice[0].square(), 3);
+        assert_eq!(advice[0].explanation(), "loses by completing row 1");
+        assert_eq!(advice[0].evaluation(), Evaluation::Loss(1));
+        assert_eq!(advice[1].explanation(), "does not complete a line");
+    }
+
+    #[test]
+    fn test_larger_board() {
+        let board = Board::parse_with_run_length("XX--/OO--/----/----", 3).unwrap();
+        let advice = Advisor::new().advise(&board);
+        let three = advice.iter().find(|advice| advice.square() == 3).unwrap();
+        assert_eq!(three.explanation(), "wins with row 1");
+    }
+} // mod hint_tests
@@ -1,11 +1,14 @@
 use crate::board::{Board, Position};
+use crate::hint::Advisor;
 use crate::player::Player;
+use crate::ultimate::{self, UltimateBoard, UltimateMove};
 
 use std::io::{BufRead, Write};
 
 pub struct HumanPlayer<'a> {
     reader: &'a mut (dyn BufRead + 'a),
     writer: &'a mut (dyn Write + 'a),
+    advisor: Advisor,
 }
 
 impl<'a> HumanPlayer<'a> {
@@ -14,7 +17,11 @@ impl<'a> HumanPlayer<'a> {
         writer: &'a mut (dyn Write + 'a),
     ) -> HumanPlayer<'a> {
         HumanPlayer::print_instructions(writer);
-        HumanPlayer { reader, writer }
+        HumanPlayer {
+            reader,
+            writer,
+            advisor: Advisor::new(),
+        }
     }
 
     fn print_instructions(writer: &'a mut (dyn Write + 'a)) {
@@ -31,8 +38,40 @@ impl<'a> HumanPlayer<'a> {
         writeln!(writer, "│456│").unwrap();
         writeln!(writer, "│789│").unwrap();
         writeln!(writer, "└───┘").unwrap();
+        writeln!(writer, "Enter '?' or 'hint' for advice.").unwrap();
         writeln!(writer).unwrap();
     }
+
+    fn read_line(&mut self) -> String {
+        let mut input = String::new();
+        let length = self
+            .reader
+            .read_line(&mut input)
+            .expect("error getting input");
+        if length == 0 {
+            panic!("No more input!");
+        }
+        String::from(input.trim())
+    }
+
+    /// Asks the question until it is answered with a number between 1 and 9,
+    /// and returns the corresponding position.
+    fn ask_for_position(&mut self, question: &str) -> Position {
+        loop {
+            writeln!(self.writer, "{}", question).unwrap();
+            match parse_position(&self.read_line()) {
+                Some(position) => return position,
+                None => writeln!(self.writer, "That is not a valid position!").unwrap(),
+            }
+        }
+    }
+
+    fn print_hints(&mut self, board: &Board) {
+        writeln!(self.writer, "Advice for each square:").unwrap();
+        for advice in self.advisor.advise(board) {
+            writeln!(self.writer, "  {}", advice).unwrap();
+        }
+    }
 }
 
 impl<'a> Player for HumanPlayer<'a> {
@@ -40,25 +79,19 @@ impl<'a> Player for HumanPlayer<'a> {
         writeln!(self.writer, "{}", board).unwrap();
         loop {
             writeln!(self.writer, "Where would you like to go? (1-9)").unwrap();
-            let mut input = String::new();
-            self.reader
-                .read_line(&mut input)
-                .expect("error getting input");
-            let input = input.trim();
-            let position = match input {
-                "1" => Position::new(0, 0),
-                "2" => Position::new(0, 1),
-                "3" => Position::new(0, 2),
-                "4" => Position::new(1, 0),
-                "5" => Position::new(1, 1),
-                "6" => Position::new(1, 2),
-                "7" => Position::new(2, 0),
-                "8" => Position::new(2, 1),
-                "9" => Position::new(2, 2),
-                &_ => {
-                    writeln!(self.writer, "That is not a valid position!").unwrap();
+            let input = self.read_line();
+            let position = match input.as_str() {
+                "?" | "hint" => {
+                    self.print_hints(board);
                     continue;
                 }
+                _ => match parse_position(&input) {
+                    Some(position) => position,
+                    None => {
+                        writeln!(self.writer, "That is not a valid position!").unwrap();
+                        continue;
+                    }
+                },
             };
 
             if board.is_position_unused(position) {
@@ -70,9 +103,58 @@ impl<'a> Player for HumanPlayer<'a> {
     }
 }
 
+/// Plays ultimate tic-tac-toe, asking for a board (when free to choose one), and then a square.
+/// Boards are numbered like the squares within them.
+impl<'a> ultimate::UltimatePlayer for HumanPlayer<'a> {
+    fn take_turn(&mut self, board: &UltimateBoard) -> UltimateMove {
+        writeln!(self.writer, "{}", board).unwrap();
+        loop {
+            let which_board = match board.next_board() {
+                Some(next_board) => {
+                    writeln!(
+                        self.writer,
+                        "You must play on board {}.",
+                        number_of(next_board)
+                    )
+                    .unwrap();
+                    next_board
+                }
+                None => self.ask_for_position("Which board would you like to play on? (1-9)"),
+            };
+            let question = format!(
+                "Where would you like to go on board {}? (1-9)",
+                number_of(which_board)
+            );
+            let square = self.ask_for_position(&question);
+
+            let their_move = UltimateMove::new(which_board, square);
+            if board.legal_moves().contains(&their_move) {
+                return their_move;
+            } else {
+                writeln!(self.writer, "That move is not allowed!").unwrap();
+            }
+        }
+    }
+}
+
+/// Converts a number between 1 and 9 into a position, counting row by row from the top left.
+fn parse_position(input: &str) -> Option<Position> {
+    match input.parse::<u8>() {
+        Ok(number) if (1..=9).contains(&number) => {
+            Some(Position::new((number - 1) / 3, (number - 1) % 3))
+        }
+        _ => None,
+    }
+}
+
+fn number_of(position: Position) -> u8 {
+    position.row() * 3 + position.column() + 1
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::board::Token;
     use std::str;
 
     #[test]
@@ -89,12 +171,66 @@ mod tests {
         let output: &str = str::from_utf8(&turn_output).unwrap();
         let output = String::from(output);
         let should_contain = "Where would you like to go?";
-        assert_eq!(
+        assert!(
             output.contains(should_contain),
-            true,
             "\nOutput did not contain '{}'\nOutput was:\n{}'",
             should_contain,
             output
         );
     }
+
+    #[test]
+    fn test_hint() {
+        let mut turn_input = b"?\nhint\n3\n" as &[u8];
+        let mut turn_output: Vec<u8> = Vec::new();
+        let mut human = HumanPlayer::new(&mut turn_input, &mut turn_output);
+
+        let board = Board::from_string(
+            "XX-\
+             OO-\
+             ---",
+        );
+        assert_eq!(human.take_turn(&board), Position::new(0, 2));
+
+        let output = str::from_utf8(&turn_output).unwrap();
+        assert_eq!(output.matches("Advice for each square:").count(), 2);
+        assert!(output.contains("3: win in 1 move - wins with row 1"));
+        assert!(output.contains("6: draw in 5 moves - blocks O's row 2"));
+        assert!(!output.contains("That is not a valid position!"));
+    }
+
+    #[test]
+    fn test_ultimate() {
+        let mut turn_input = b"0\n5\n1\n1\n3\n" as &[u8];
+        let mut turn_output: Vec<u8> = Vec::new();
+        let mut human = HumanPlayer::new(&mut turn_input, &mut turn_output);
+
+        let mut board = UltimateBoard::new();
+        let center = Position::new(1, 1);
+        let corner = Position::new(0, 0);
+        let first = ultimate::UltimatePlayer::take_turn(&mut human, &board);
+        assert_eq!(first, UltimateMove::new(center, corner));
+        board.add_move(Token::X, first);
+        board.add_move(Token::O, UltimateMove::new(corner, center));
+
+        // X is sent back to the center, where the corner is already taken.
+        let second = ultimate::UltimatePlayer::take_turn(&mut human, &board);
+        assert_eq!(second, UltimateMove::new(center, Position::new(0, 2)));
+
+        let output = str::from_utf8(&turn_output).unwrap();
+        assert!(output.contains("Which board would you like to play on? (1-9)"));
+        assert_eq!(output.matches("That is not a valid position!").count(), 1);
+        assert!(output.contains("You must play on board 5."));
+        assert!(output.contains("Where would you like to go on board 5? (1-9)"));
+        assert_eq!(output.matches("That move is not allowed!").count(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "No more input!")]
+    fn test_end_of_input() {
+        let mut turn_input = b"" as &[u8];
+        let mut turn_output: Vec<u8> = Vec::new();
+        let mut human = HumanPlayer::new(&mut turn_input, &mut turn_output);
+        human.take_turn(&Board::new());
+    }
 }
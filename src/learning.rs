@@ -0,0 +1,583 @@
+//! A player that learns to play by reinforcement, from games against itself or other players.
+//!
+//! The player keeps a table of values, one for each position it has moved to: the chance that
+//! it goes on to win from there, counting a draw as half a win.  It moves to the position with
+//! the highest value, and after each move of a training game it nudges the value of its previous
+//! position towards the value of its new one, which is temporal difference learning, TD(0).
+//! Positions that are rotations or reflections of each other share a value.
+use crate::board::{Board, GameResult, Position, Token};
+use crate::player::Player;
+use crate::saved::{self, Format, LoadError};
+
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+/// How far each value moves towards its target after a move, unless set otherwise.
+pub const DEFAULT_LEARNING_RATE: f64 = 0.2;
+
+/// How often a move is chosen at random while training, unless set otherwise.
+pub const DEFAULT_EXPLORATION: f64 = 0.1;
+
+/// The value of a position the player has not learned anything about.
+const UNKNOWN_VALUE: f64 = 0.5;
+
+/// The format of a saved value table.
+const FORMAT: Format = Format {
+    header: "tictactoe values",
+    version: 1,
+    name: "value table",
+};
+
+/// How the training games of one epoch ended, for the learning player.
+///
+/// In self-play the player is on both sides, and the games are counted for X.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct EpochResult {
+    pub wins: u64,
+    pub draws: u64,
+    pub losses: u64,
+}
+
+impl EpochResult {
+    /// Returns the number of games counted.
+    pub fn games(self) -> u64 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Returns the fraction of the games that were won.
+    pub fn win_rate(self) -> f64 {
+        self.wins as f64 / self.games().max(1) as f64
+    }
+
+    /// Returns the fraction of the games that were drawn.
+    pub fn draw_rate(self) -> f64 {
+        self.draws as f64 / self.games().max(1) as f64
+    }
+
+    /// Returns the fraction of the games that were lost.
+    pub fn loss_rate(self) -> f64 {
+        self.losses as f64 / self.games().max(1) as f64
+    }
+
+    pub(crate) fn add(&mut self, result: &GameResult, token: Token) {
+        match result {
+            GameResult::Win(winner) if *winner == token => self.wins += 1,
+            GameResult::Win(_winner) => self.losses += 1,
+            GameResult::Draw => self.draws += 1,
+            GameResult::InProgress => panic!("Game should be over!"),
+        }
+    }
+}
+
+/// The results of a training session, epoch by epoch: the player's learning curve.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TrainingReport {
+    epochs: Vec<EpochResult>,
+}
+
+impl TrainingReport {
+    /// Returns the results of every epoch, in order.
+    pub fn epochs(&self) -> &[EpochResult] {
+        &self.epochs
+    }
+
+    /// Returns the results of the last epoch, or None if there were none.
+    pub fn last(&self) -> Option<EpochResult> {
+        self.epochs.last().copied()
+    }
+
+    pub(crate) fn push(&mut self, epoch: EpochResult) {
+        self.epochs.push(epoch);
+    }
+}
+
+impl std::fmt::Display for TrainingReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "Epoch      Games       Wins      Draws     Losses")?;
+        for (number, epoch) in self.epochs.iter().enumerate() {
+            writeln!(
+                f,
+                "{:>5} {:>10} {:>9.1}% {:>9.1}% {:>9.1}%",
+                number + 1,
+                epoch.games(),
+                100.0 * epoch.win_rate(),
+                100.0 * epoch.draw_rate(),
+                100.0 * epoch.loss_rate()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A player that learns the value of positions by playing training games.
+///
+/// It learns on the standard 3x3 board.  Outside of training it always makes the move
+/// it values most, so it plays the same way every time.
+///
+/// # Examples
+/// ```
+/// use tictactoelib::learning::LearningPlayer;
+/// use tictactoelib::strategies::RandomPlayer;
+///
+/// let mut player = LearningPlayer::with_seed(1);
+/// let report = player.train_against(&mut RandomPlayer::with_seed(2), 5, 200);
+/// assert_eq!(report.epochs().len(), 5);
+/// let (first, last) = (report.epochs()[0], report.last().unwrap());
+/// assert!(last.loss_rate() < first.loss_rate());
+/// ```
+pub struct LearningPlayer<R: RngCore = StdRng> {
+    values: HashMap<Board, f64>,
+    learning_rate: f64,
+    exploration: f64,
+    rng: R,
+}
+
+impl LearningPlayer {
+    /// Creates an untrained player seeded from the operating system's entropy source.
+    pub fn new() -> LearningPlayer {
+        LearningPlayer::with_rng(StdRng::from_entropy())
+    }
+
+    /// Creates an untrained player whose training is reproducible for a given seed.
+    pub fn with_seed(seed: u64) -> LearningPlayer {
+        LearningPlayer::with_rng(StdRng::seed_from_u64(seed))
+    }
+}
+
+impl Default for LearningPlayer {
+    fn default() -> LearningPlayer {
+        LearningPlayer::new()
+    }
+}
+
+impl<R: RngCore> LearningPlayer<R> {
+    /// Creates an untrained player that explores with the given random number generator.
+    pub fn with_rng(rng: R) -> LearningPlayer<R> {
+        LearningPlayer {
+            values: HashMap::new(),
+            learning_rate: DEFAULT_LEARNING_RATE,
+            exploration: DEFAULT_EXPLORATION,
+            rng,
+        }
+    }
+
+    /// Returns how far each value moves towards its target after a move.
+    pub fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    /// Changes the learning rate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rate is not more than zero and at most one.
+    pub fn set_learning_rate(&mut self, learning_rate: f64) {
+        if !(learning_rate > 0.0 && learning_rate <= 1.0) {
+            panic!("Invalid learning rate: {}", learning_rate);
+        }
+        self.learning_rate = learning_rate;
+    }
+
+    /// Returns how often a move is chosen at random while training.
+    pub fn exploration(&self) -> f64 {
+        self.exploration
+    }
+
+    /// Changes how often a move is chosen at random while training.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rate is not between zero and one.
+    pub fn set_exploration(&mut self, exploration: f64) {
+        if !(0.0..=1.0).contains(&exploration) {
+            panic!("Invalid exploration rate: {}", exploration);
+        }
+        self.exploration = exploration;
+    }
+
+    /// Returns the number of positions the player has learned a value for.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns whether the player has learned nothing yet.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns the learned chance that the player who made the last move on the board
+    /// goes on to win, counting a draw as half a win.
+    ///
+    /// Finished games have their true value, and positions that have not been learned are 0.5.
+    ///
+    /// # Examples
+    /// ```
+    /// use tictactoelib::board::Board;
+    /// use tictactoelib::learning::LearningPlayer;
+    ///
+    /// let player = LearningPlayer::with_seed(0);
+    /// assert_eq!(player.value(&"XXX/OO-/---".parse().unwrap()), 1.0);
+    /// assert_eq!(player.value(&"X--/---/---".parse().unwrap()), 0.5);
+    /// ```
+    pub fn value(&self, board: &Board) -> f64 {
+        match board.get_game_result() {
+            GameResult::Win(winner) if winner == last_mover(board) => 1.0,
+            GameResult::Win(_winner) => 0.0,
+            GameResult::Draw => 0.5,
+            GameResult::InProgress => *self
+                .values
+                .get(&board.canonical().0)
+                .unwrap_or(&UNKNOWN_VALUE),
+        }
+    }
+
+    /// Plays training games against the opponent, with the player taking X and O in turn,
+    /// and returns how the games of each epoch ended.
+    pub fn train_against(
+        &mut self,
+        opponent: &mut impl Player,
+        epochs: u32,
+        games_per_epoch: u32,
+    ) -> TrainingReport {
+        self.train(Some(opponent), epochs, games_per_epoch)
+    }
+
+    /// Plays training games against itself, learning from both sides,
+    /// and returns how the games of each epoch ended for X.
+    pub fn train_by_self_play(&mut self, epochs: u32, games_per_epoch: u32) -> TrainingReport {
+        self.train(None, epochs, games_per_epoch)
+    }
+
+    fn train(
+        &mut self,
+        mut opponent: Option<&mut (dyn Player + '_)>,
+        epochs: u32,
+        games_per_epoch: u32,
+    ) -> TrainingReport {
+        let mut report = TrainingReport::default();
+        for _epoch in 0..epochs {
+            let mut epoch = EpochResult::default();
+            for game in 0..games_per_epoch {
+                let token = if game % 2 == 0 { Token::X } else { Token::O };
+                let result = self.play_training_game(opponent.as_deref_mut(), token);
+                epoch.add(&result, token);
+            }
+            report.push(epoch);
+        }
+        report
+    }
+
+    /// Plays one game, learning from the moves of the given token,
+    /// or of both tokens if there is no opponent.
+    fn play_training_game(
+        &mut self,
+        mut opponent: Option<&mut (dyn Player + '_)>,
+        token: Token,
+    ) -> GameResult {
+        let mut board = Board::new();
+        // The position each learning side last moved to, if the game was not over.
+        let mut previous: HashMap<Token, Board> = HashMap::new();
+        while let Some(turn) = board.whose_turn() {
+            let position = match opponent.as_deref_mut() {
+                Some(opponent) if turn != token => {
+                    board.add_move(turn, opponent.take_turn(&board));
+                    continue;
+                }
+                _ => self.explore(&board),
+            };
+            let explored = position.is_none();
+            let position = position.unwrap_or_else(|| self.random_move(&board));
+            board.add_move(turn, position);
+
+            if !explored {
+                if let Some(before) = previous.get(&turn) {
+                    let target = self.value(&board);
+                    self.learn(*before, target);
+                }
+            }
+            if board.get_game_result() == GameResult::InProgress {
+                previous.insert(turn, board.canonical().0);
+            } else {
+                previous.remove(&turn);
+            }
+        }
+
+        // Each side that is still waiting to learn from its last move learns how the game ended.
+        let result = board.get_game_result();
+        for (side, before) in previous {
+            let target = match result {
+                GameResult::Win(winner) if winner == side => 1.0,
+                GameResult::Win(_winner) => 0.0,
+                _ => 0.5,
+            };
+            self.learn(before, target);
+        }
+        result
+    }
+
+    /// Returns the best move, or None if the player should explore instead.
+    fn explore(&mut self, board: &Board) -> Option<Position> {
+        if self.rng.gen::<f64>() < self.exploration {
+            None
+        } else {
+            Some(self.best_move(board))
+        }
+    }
+
+    fn random_move(&mut self, board: &Board) -> Position {
+        let empty_positions = board.empty_positions();
+        empty_positions[self.rng.gen_range(0, empty_positions.len())]
+    }
+
+    /// Returns the move to the position with the highest value, the first one if there is a tie.
+    fn best_move(&self, board: &Board) -> Position {
+        let player = board.whose_turn().expect("Game should not be over!");
+        let mut best: Option<(Position, f64)> = None;
+        for position in board.empty_positions_iter() {
+            let mut next = *board;
+            next.add_move(player, position);
+            let value = self.value(&next);
+            if best.map_or(true, |(_position, best)| value > best) {
+                best = Some((position, value));
+            }
+        }
+        best.expect("There are no moves to make!").0
+    }
+
+    /// Moves the value of a canonical position towards the target.
+    fn learn(&mut self, canonical: Board, target: f64) {
+        let learning_rate = self.learning_rate;
+        let value = self.values.entry(canonical).or_insert(UNKNOWN_VALUE);
+        *value += learning_rate * (target - *value);
+    }
+
+    /// Writes the learned values as text: a header line, then one position and value per line.
+    pub fn write_to(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        let lines = self
+            .values
+            .iter()
+            .map(|(board, value)| format!("{} {}", board.to_compact_string(), value))
+            .collect();
+        FORMAT.write(writer, lines)
+    }
+
+    /// Replaces the learned values with those written by write_to().
+    pub fn read_from(&mut self, reader: &mut impl BufRead) -> Result<(), LoadError> {
+        let values = FORMAT.read(reader, |line| {
+            let mut words = line.split_whitespace();
+            let board = words
+                .next()
+                .and_then(|board| board.parse::<Board>().ok())
+                .filter(|board| board.rows() == 3 && board.columns() == 3)?;
+            let value = words
+                .next()
+                .and_then(|value| value.parse::<f64>().ok())
+                .filter(|value| (0.0..=1.0).contains(value))?;
+            match words.next() {
+                None => Some((board.canonical().0, value)),
+                Some(_extra) => None,
+            }
+        })?;
+        self.values = values.into_iter().collect();
+        Ok(())
+    }
+
+    /// Saves the learned values to a file.
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        saved::save(path, |file| self.write_to(file))
+    }
+
+    /// Replaces the learned values with those saved by save().
+    pub fn load(&mut self, path: impl AsRef<Path>) -> Result<(), LoadError> {
+        saved::load(path, |file| self.read_from(file))
+    }
+}
+
+/// Returns the token that made the last move on the board: X always moves first.
+fn last_mover(board: &Board) -> Token {
+    let squares = board.rows() as usize * board.columns() as usize;
+    if (squares - board.empty_positions_iter().count()) % 2 == 1 {
+        Token::X
+    } else {
+        Token::O
+    }
+}
+
+impl<R: RngCore> Player for LearningPlayer<R> {
+    fn take_turn(&mut self, board: &Board) -> Position {
+        self.best_move(board)
+    }
+}
+
+#[cfg(test)]
+mod learning_tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::optimal::OptimalPlayer;
+    use crate::strategies::RandomPlayer;
+
+    /// Trains a player well enough to hold its own against a perfect opponent.
+    fn trained_player() -> LearningPlayer {
+        let mut player = LearningPlayer::with_seed(1);
+        player.train_by_self_play(4, 1000);
+        player.train_against(&mut RandomPlayer::with_seed(2), 2, 500);
+        player.train_against(&mut OptimalPlayer::new(), 5, 100);
+        player
+    }
+
+    #[test]
+    fn test_no_losses_against_optimal_after_training() {
+        let mut optimal = OptimalPlayer::new();
+        let mut untrained = LearningPlayer::with_seed(1);
+        let game = Game::new(&mut untrained, &mut optimal);
+        assert_eq!(game.result(), GameResult::Win(Token::O));
+
+        let mut player = trained_player();
+        let game = Game::new(&mut player, &mut optimal);
+        assert_ne!(game.result(), GameResult::Win(Token::O), "{}", game.board());
+        let game = Game::new(&mut optimal, &mut player);
+        assert_ne!(game.result(), GameResult::Win(Token::X), "{}", game.board());
+
+        // With exploration turned off, training games against it are all drawn.
+        player.set_exploration(0.0);
+        let report = player.train_against(&mut optimal, 1, 10);
+        assert_eq!(report.last().unwrap().losses, 0);
+    }
+
+    #[test]
+    fn test_learning_curve() {
+        let mut player = LearningPlayer::with_seed(3);
+        let report = player.train_against(&mut RandomPlayer::with_seed(4), 4, 500);
+        assert_eq!(report.epochs().len(), 4);
+        for epoch in report.epochs() {
+            assert_eq!(epoch.games(), 500);
+        }
+        let (first, last) = (report.epochs()[0], report.last().unwrap());
+        assert_gt!(last.win_rate(), first.win_rate());
+        assert!(!player.is_empty());
+    }
+
+    #[test]
+    fn test_learns_to_win() {
+        let mut player = LearningPlayer::with_seed(5);
+        player.train_by_self_play(5, 1000);
+        let board = Board::from_string(
+            "XX-\
+             OO-\
+             ---",
+        );
+        assert_eq!(player.take_turn(&board), Position::new(0, 2));
+    }
+
+    #[test]
+    fn test_same_seed_same_values() {
+        let mut first = LearningPlayer::with_seed(6);
+        let mut second = LearningPlayer::with_seed(6);
+        assert_eq!(
+            first.train_by_self_play(2, 100),
+            second.train_by_self_play(2, 100)
+        );
+        assert_eq!(first.values, second.values);
+    }
+
+    #[test]
+    fn test_report_display() {
+        let report = TrainingReport {
+            epochs: vec![
+                EpochResult {
+                    wins: 5,
+                    draws: 3,
+                    losses: 2,
+                },
+                EpochResult {
+                    wins: 8,
+                    draws: 2,
+                    losses: 0,
+                },
+            ],
+        };
+        assert_eq!(
+            report.to_string(),
+            "Epoch      Games       Wins      Draws     Losses\n\
+             \x20   1         10      50.0%      30.0%      20.0%\n\
+             \x20   2         10      80.0%      20.0%       0.0%\n"
+        );
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let mut player = LearningPlayer::with_seed(7);
+        player.train_by_self_play(1, 200);
+        let mut saved = Vec::new();
+        player.write_to(&mut saved).unwrap();
+
+        let mut loaded = LearningPlayer::with_seed(8);
+        loaded.read_from(&mut saved.as_slice()).unwrap();
+        assert_eq!(loaded.values, player.values);
+
+        let path = std::env::temp_dir().join("tictactoe_learning_test.values");
+        player.save(&path).unwrap();
+        let mut loaded = LearningPlayer::with_seed(9);
+        loaded.load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.values, player.values);
+    }
+
+    #[test]
+    fn test_load_errors() {
+        let mut player = LearningPlayer::with_seed(0);
+        let read = |player: &mut LearningPlayer, text: &str| {
+            player
+                .read_from(&mut text.as_bytes())
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(read(&mut player, ""), "Not a value table file");
+        assert_eq!(
+            read(&mut player, "tictactoe tablebase"),
+            "Not a value table file"
+        );
+        assert_eq!(
+            read(&mut player, "tictactoe values 2\n"),
+            "Unsupported value table version 2"
+        );
+        assert_eq!(
+            read(
+                &mut player,
+                "tictactoe values 1\nX-------- 0.5\nX-------- 1.5\n"
+            ),
+            "Invalid value table line 3"
+        );
+        assert_eq!(
+            read(&mut player, "tictactoe values 1\nX-------- 0.5 extra\n"),
+            "Invalid value table line 2"
+        );
+        assert!(player.is_empty());
+    }
+
+    #[test]
+    fn test_settings() {
+        let mut player = LearningPlayer::with_seed(0);
+        assert_eq!(player.learning_rate(), DEFAULT_LEARNING_RATE);
+        assert_eq!(player.exploration(), DEFAULT_EXPLORATION);
+        player.set_learning_rate(0.5);
+        player.set_exploration(0.0);
+        assert_eq!(player.learning_rate(), 0.5);
+        assert_eq!(player.exploration(), 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid learning rate: 0")]
+    fn test_invalid_learning_rate() {
+        LearningPlayer::with_seed(0).set_learning_rate(0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid exploration rate: 1.5")]
+    fn test_invalid_exploration() {
+        LearningPlayer::with_seed(0).set_exploration(1.5);
+    }
+} // mod learning_tests
@@ -10,44 +10,338 @@
 extern crate more_asserts;
 
 // Modules needed for benchmarking are public
+pub mod alphabeta;
+pub mod arena;
 pub mod board;
-mod game;
+mod cli;
+pub mod computer;
+pub mod game;
+pub mod heuristic;
+pub mod hint;
 mod human;
+pub mod learning;
+pub mod mcts;
+pub mod menace;
 pub mod optimal;
 pub mod player;
-#[cfg(test)]
-pub mod strategies; // Only for unit testing
+pub mod record;
+pub mod saved;
+pub mod simulation;
+pub mod strategies;
+pub mod tablebase;
+pub mod ultimate;
+pub mod verification;
 
-use crate::board::{GameResult, Token};
-use crate::game::Game;
+use crate::alphabeta::{AlphaBetaPlayer, LineEvaluator};
+use crate::board::{Board, GameResult, Token};
+use crate::cli::{Command, PlayOptions, PlayerKind, SimulateOptions, UltimateOptions};
+use crate::computer::{ComputerPlayer, Difficulty};
+use crate::game::{ConsoleObserver, Game};
+use crate::heuristic::HeuristicPlayer;
 use crate::human::HumanPlayer;
+use crate::mcts::{Budget, MctsPlayer};
 use crate::optimal::OptimalPlayer;
+use crate::player::Player;
+use crate::record::format_position;
+use crate::simulation::Simulation;
+use crate::strategies::{Preference, RandomPlayer, WeightedRandomPlayer};
+use crate::ultimate::{UltimateGame, UltimatePlayer};
+
+use std::io::{BufReader, Stdin, Stdout};
+use std::time::Duration;
+
+/// The exit code for a command line that cannot be understood.
+const EXIT_USAGE: i32 = 2;
+/// The exit code for a command that was understood, but failed.
+const EXIT_FAILURE: i32 = 1;
+
+/// How long an MCTS player chosen on the command line searches before each move.
+const MCTS_BUDGET: Budget = Budget::Iterations(2000);
+/// How long an alpha-beta player chosen on the command line searches before each move.
+const ALPHA_BETA_TIME_LIMIT: Duration = Duration::from_secs(1);
+/// The most squares a board can have for the analyze command to solve it in reasonable time.
+const MAX_ANALYZE_SQUARES: usize = 16;
 
 /// The entry point for the "library", which implements the game.
+///
+/// Exits the process with a non-zero code if the command line is invalid,
+/// or the command fails.
 pub fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match cli::parse_args(&args) {
+        Ok(command) => command,
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            eprintln!();
+            eprintln!("{}", cli::USAGE);
+            std::process::exit(EXIT_USAGE);
+        }
+    };
+
+    let outcome = match command {
+        Command::Interactive(level) => {
+            play_interactive(level);
+            Ok(())
+        }
+        Command::Play(options) => {
+            play_games(&options);
+            Ok(())
+        }
+        Command::Simulate(options) => {
+            simulate(&options);
+            Ok(())
+        }
+        Command::Analyze(board) => analyze(&board),
+        Command::Ultimate(options) => {
+            play_ultimate(&options);
+            Ok(())
+        }
+        Command::Help => {
+            println!("{}", cli::USAGE);
+            Ok(())
+        }
+    };
+    if let Err(message) = outcome {
+        eprintln!("Error: {}", message);
+        std::process::exit(EXIT_FAILURE);
+    }
+}
+
+fn play_interactive(level: Difficulty) {
     println!("Tic-Tac-Toe");
     println!("In this version, X always plays first.");
 
-    let human_token = get_player();
+    let human_token = get_player(level);
 
     let stdin = std::io::stdin();
     let mut input = stdin.lock();
     let mut output = std::io::stdout();
     let human = HumanPlayer::new(&mut input, &mut output);
-    let computer = OptimalPlayer {};
+    let computer = ComputerPlayer::new(level);
 
-    let game = if human_token == Token::X {
-        Game::new(human, computer)
+    let result = if human_token == Token::X {
+        play(Game::start(human, computer))
     } else {
-        Game::new(computer, human)
+        play(Game::start(computer, human))
     };
 
-    display_result(game, human_token);
+    display_result(result, human_token);
+}
+
+/// The console a human player reads moves from and writes prompts to.
+///
+/// Each human gets their own, so that two humans can play each other.
+/// Reading a byte at a time leaves the rest of stdin for the other player.
+struct Console {
+    reader: BufReader<Stdin>,
+    writer: Stdout,
 }
 
-fn get_player() -> Token {
-    println!("You cannot win!");
-    println!("ᕙ(⇀‸↼‶)ᕗ");
+impl Console {
+    fn new() -> Console {
+        Console {
+            reader: BufReader::with_capacity(1, std::io::stdin()),
+            writer: std::io::stdout(),
+        }
+    }
+}
+
+fn create_player(
+    kind: PlayerKind,
+    level: Difficulty,
+    seed: Option<u64>,
+    console: &mut Console,
+) -> Box<dyn Player + '_> {
+    match kind {
+        PlayerKind::Human => Box::new(HumanPlayer::new(&mut console.reader, &mut console.writer)),
+        _ => create_computer_player(kind, level, seed),
+    }
+}
+
+/// Creates any kind of player except a human, who needs a console.
+fn create_computer_player(
+    kind: PlayerKind,
+    level: Difficulty,
+    seed: Option<u64>,
+) -> Box<dyn Player> {
+    match kind {
+        PlayerKind::Human => panic!("A human player needs a console!"),
+        PlayerKind::Computer => match seed {
+            Some(seed) => Box::new(ComputerPlayer::with_seed(level, seed)),
+            None => Box::new(ComputerPlayer::new(level)),
+        },
+        PlayerKind::Optimal => Box::new(OptimalPlayer::new()),
+        PlayerKind::Random => match seed {
+            Some(seed) => Box::new(RandomPlayer::with_seed(seed)),
+            None => Box::new(RandomPlayer::new()),
+        },
+        PlayerKind::Center => create_weighted_player(Preference::Center, seed),
+        PlayerKind::Corners => create_weighted_player(Preference::Corners, seed),
+        PlayerKind::Heuristic => Box::new(HeuristicPlayer::new()),
+        PlayerKind::Mcts => match seed {
+            Some(seed) => Box::new(MctsPlayer::with_seed(MCTS_BUDGET, seed)),
+            None => Box::new(MctsPlayer::new(MCTS_BUDGET)),
+        },
+        PlayerKind::AlphaBeta => {
+            let mut player = AlphaBetaPlayer::new(LineEvaluator);
+            player.set_time_limit(ALPHA_BETA_TIME_LIMIT);
+            Box::new(player)
+        }
+    }
+}
+
+fn create_weighted_player(preference: Preference, seed: Option<u64>) -> Box<dyn Player> {
+    match seed {
+        Some(seed) => Box::new(WeightedRandomPlayer::with_seed(preference, seed)),
+        None => Box::new(WeightedRandomPlayer::new(preference)),
+    }
+}
+
+fn play_games(options: &PlayOptions) {
+    // X and O get different seeds, so that two random players do not mirror each other.
+    let x_seed = options.seed;
+    let o_seed = options.seed.map(|seed| seed.wrapping_add(1));
+    let mut x_console = Console::new();
+    let mut o_console = Console::new();
+    let mut x = create_player(options.x, options.level, x_seed, &mut x_console);
+    let mut o = create_player(options.o, options.level, o_seed, &mut o_console);
+
+    let start = if options.misere {
+        Board::misere()
+    } else {
+        Board::new()
+    };
+    if options.misere && !options.quiet {
+        println!("Misère rules: whoever completes a line loses!");
+    }
+
+    let (mut x_wins, mut o_wins, mut draws) = (0, 0, 0);
+    for number in 1..=options.games {
+        let mut game = Game::start_from(start, &mut x, &mut o);
+        if !options.quiet {
+            if options.games > 1 {
+                println!("Game #{}", number);
+            }
+            game.add_observer(ConsoleObserver {});
+        }
+        let result = game.play_to_end();
+        match result {
+            GameResult::Win(Token::X) => x_wins += 1,
+            GameResult::Win(Token::O) => o_wins += 1,
+            _ => draws += 1,
+        }
+        if !options.quiet {
+            match result {
+                GameResult::Win(winner) => println!("{} wins!", winner),
+                _ => println!("It is a draw."),
+            }
+            println!();
+        }
+    }
+
+    if options.quiet || options.games > 1 {
+        println!(
+            "Games: {}, X wins: {}, O wins: {}, draws: {}",
+            options.games, x_wins, o_wins, draws
+        );
+    }
+}
+
+fn play_ultimate(options: &UltimateOptions) {
+    let mut x_console = Console::new();
+    let mut o_console = Console::new();
+    let x_seed = options.seed;
+    let o_seed = options.seed.map(|seed| seed.wrapping_add(1));
+    let x = create_ultimate_player(options.x, x_seed, &mut x_console);
+    let o = create_ultimate_player(options.o, o_seed, &mut o_console);
+
+    let mut game = UltimateGame::new(x, o);
+    while let Some(their_move) = game.step() {
+        let player = if game.board().moves().len() % 2 == 0 {
+            Token::O
+        } else {
+            Token::X
+        };
+        println!("{} plays {}", player, their_move);
+    }
+    println!("{}", game.board());
+    match game.result() {
+        GameResult::Win(winner) => println!("{} wins!", winner),
+        _ => println!("It is a draw."),
+    }
+}
+
+fn create_ultimate_player<'a>(
+    kind: PlayerKind,
+    seed: Option<u64>,
+    console: &'a mut Console,
+) -> Box<dyn UltimatePlayer + 'a> {
+    match kind {
+        PlayerKind::Human => Box::new(HumanPlayer::new(&mut console.reader, &mut console.writer)),
+        PlayerKind::Random => match seed {
+            Some(seed) => Box::new(RandomPlayer::with_seed(seed)),
+            None => Box::new(RandomPlayer::new()),
+        },
+        _ => panic!("A {:?} player cannot play ultimate tic-tac-toe!", kind),
+    }
+}
+
+fn simulate(options: &SimulateOptions) {
+    let mut simulation = Simulation::new(options.games, options.seed);
+    if let Some(threads) = options.threads {
+        simulation.set_threads(threads);
+    }
+    let report = simulation.run(
+        |seed| create_computer_player(options.x, options.level, Some(seed)),
+        |seed| create_computer_player(options.o, options.level, Some(seed)),
+    );
+    print!("{}", report);
+}
+
+fn analyze(board: &str) -> Result<(), String> {
+    let board: Board = board
+        .parse()
+        .map_err(|error| format!("Invalid board: {}", error))?;
+    let squares = board.rows() as usize * board.columns() as usize;
+    if squares > MAX_ANALYZE_SQUARES {
+        return Err(format!(
+            "Board too large to analyze: {}x{} has {} squares, but at most {} can be solved",
+            board.rows(),
+            board.columns(),
+            squares,
+            MAX_ANALYZE_SQUARES
+        ));
+    }
+    println!("{}", board);
+    match board.get_game_result() {
+        GameResult::Win(winner) => println!("{} has won.", winner),
+        GameResult::Draw => println!("The game is a draw."),
+        GameResult::InProgress => {
+            let mut player = OptimalPlayer::new();
+            let token = board.whose_turn().unwrap();
+            println!("{} to play: {}", token, player.evaluate(&board));
+            let mut evaluations = player.evaluate_moves(&board);
+            evaluations.sort_by_key(|(_position, evaluation)| -evaluation.score());
+            for (position, evaluation) in evaluations {
+                println!("{:>4}  {}", format_position(position), evaluation);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn play(mut game: Game<impl Player, impl Player>) -> GameResult {
+    game.add_observer(ConsoleObserver {});
+    game.play_to_end()
+}
+
+fn get_player(level: Difficulty) -> Token {
+    if level == Difficulty::Perfect {
+        println!("You cannot win!");
+        println!("ᕙ(⇀‸↼‶)ᕗ");
+    } else {
+        println!("I am playing at the {} level.  Can you beat me?", level);
+    }
     println!("Do you want to be X or O?");
 
     loop {
@@ -68,8 +362,8 @@ fn get_player() -> Token {
     }
 }
 
-fn display_result(game: Game, human_token: Token) {
-    match game.result() {
+fn display_result(result: GameResult, human_token: Token) {
+    match result {
         GameResult::Draw => {
             println!("¯\\_(ツ)_/¯");
             println!("It is a draw?");
@@ -78,7 +372,9 @@ fn display_result(game: Game, human_token: Token) {
         GameResult::InProgress => panic!("Should not happen!"),
         GameResult::Win(winner) => {
             if winner == human_token {
-                panic!("What??   I cannot lose!!!");
+                println!("\\(^o^)/");
+                println!("You win!  Well played!");
+                println!("\\(^o^)/");
             } else {
                 println!("ᕙ(⇀‸↼‶)ᕗ");
                 println!("Ha!  I beat you!");
@@ -0,0 +1,323 @@
+//! A Monte Carlo Tree Search player, for boards too large to solve.
+//!
+//! Each turn grows a search tree from the current board, one node per iteration.
+//! Moves are selected by UCT (upper confidence bounds applied to trees), which balances
+//! moves that have done well so far against moves that have been tried less often,
+//! and each new node is valued by playing the game out at random.
+use crate::board::{Board, GameResult, Position, Token};
+use crate::player::Player;
+
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+use std::time::{Duration, Instant};
+
+/// How long an MctsPlayer searches before each move.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Budget {
+    /// Grow the tree by this many nodes.
+    Iterations(u32),
+    /// Keep searching until this much time has passed.
+    Time(Duration),
+}
+
+/// The exploration constant usually used with UCT: the square root of two.
+pub const DEFAULT_EXPLORATION: f64 = std::f64::consts::SQRT_2;
+
+/// One position in the search tree.
+struct Node {
+    /// The move that led here from the parent, or None for the root.
+    played: Option<Position>,
+    /// The player who made that move.
+    player: Token,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// The moves that do not have a child node yet.
+    untried: Vec<Position>,
+    visits: u32,
+    /// The total score of the playouts through this node, for the player who moved here:
+    /// 1 for each win and 0.5 for each draw.
+    score: f64,
+}
+
+impl Node {
+    fn new(played: Option<Position>, player: Token, parent: Option<usize>, board: &Board) -> Node {
+        let untried = if board.get_game_result() == GameResult::InProgress {
+            board.empty_positions()
+        } else {
+            Vec::new()
+        };
+        Node {
+            played,
+            player,
+            parent,
+            children: Vec::new(),
+            untried,
+            visits: 0,
+            score: 0.0,
+        }
+    }
+
+    /// Returns the node's upper confidence bound, for a parent with the given number of visits.
+    fn uct(&self, parent_visits: u32, exploration: f64) -> f64 {
+        let visits = f64::from(self.visits);
+        self.score / visits + exploration * (f64::from(parent_visits).ln() / visits).sqrt()
+    }
+}
+
+/// A player that chooses its moves by Monte Carlo Tree Search.
+///
+/// It plays on any size of board, by either rules, and grows stronger with a larger budget.
+///
+/// # Examples
+/// ```
+/// use tictactoelib::board::{Board, Position};
+/// use tictactoelib::mcts::{Budget, MctsPlayer};
+/// use tictactoelib::player::Player;
+///
+/// let mut player = MctsPlayer::with_seed(Budget::Iterations(1000), 7);
+/// let board: Board = "XX-/OO-/---".parse().unwrap();
+/// assert_eq!(player.take_turn(&board), Position::new(0, 2));
+/// assert_eq!(player.last_iterations(), 1000);
+/// ```
+pub struct MctsPlayer<R: RngCore = StdRng> {
+    budget: Budget,
+    exploration: f64,
+    rng: R,
+    last_iterations: u32,
+}
+
+impl MctsPlayer {
+    /// Creates a player seeded from the operating system's entropy source.
+    pub fn new(budget: Budget) -> MctsPlayer {
+        MctsPlayer::with_rng(budget, StdRng::from_entropy())
+    }
+
+    /// Creates a player whose moves are reproducible for a given seed,
+    /// as long as its budget is a number of iterations.
+    pub fn with_seed(budget: Budget, seed: u64) -> MctsPlayer {
+        MctsPlayer::with_rng(budget, StdRng::seed_from_u64(seed))
+    }
+}
+
+impl<R: RngCore> MctsPlayer<R> {
+    /// Creates a player that draws its playouts from the given random number generator.
+    pub fn with_rng(budget: Budget, rng: R) -> MctsPlayer<R> {
+        MctsPlayer {
+            budget,
+            exploration: DEFAULT_EXPLORATION,
+            rng,
+            last_iterations: 0,
+        }
+    }
+
+    /// Returns how long the player searches before each move.
+    pub fn budget(&self) -> Budget {
+        self.budget
+    }
+
+    /// Returns the exploration constant:
+    /// the larger it is, the more the search favours moves it has tried less often.
+    pub fn exploration(&self) -> f64 {
+        self.exploration
+    }
+
+    /// Changes the exploration constant.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the constant is negative, or not a number.
+    pub fn set_exploration(&mut self, exploration: f64) {
+        if exploration.is_nan() || exploration < 0.0 {
+            panic!("Invalid exploration constant: {}", exploration);
+        }
+        self.exploration = exploration;
+    }
+
+    /// Returns the number of iterations of the search for the player's last move.
+    pub fn last_iterations(&self) -> u32 {
+        self.last_iterations
+    }
+
+    /// Searches from the board, and returns the move that was explored the most.
+    fn search(&mut self, board: &Board) -> Position {
+        let me = board.whose_turn().expect("Game should not be over!");
+        let mut tree = vec![Node::new(None, me.opponent(), None, board)];
+        let start = Instant::now();
+        let mut iterations = 0;
+        loop {
+            let done = match self.budget {
+                Budget::Iterations(limit) => iterations >= limit.max(1),
+                Budget::Time(limit) => iterations > 0 && start.elapsed() >= limit,
+            };
+            if done {
+                break;
+            }
+            self.iterate(&mut tree, board);
+            iterations += 1;
+        }
+        self.last_iterations = iterations;
+
+        let best = tree[0]
+            .children
+            .iter()
+            .max_by_key(|child| tree[**child].visits)
+            .expect("The search should have expanded the root!");
+        tree[*best].played.unwrap()
+    }
+
+    /// Selects a path down the tree, expands it by one node, plays out a game from there,
+    /// and records the result on every node of the path.
+    fn iterate(&mut self, tree: &mut Vec<Node>, root: &Board) {
+        let mut board = *root;
+        let mut node = 0;
+
+        // Selection: descend through fully expanded nodes.
+        while tree[node].untried.is_empty() && !tree[node].children.is_empty() {
+            let parent_visits = tree[node].visits;
+            let exploration = self.exploration;
+            node = *tree[node]
+                .children
+                .iter()
+                .max_by(|a, b| {
+                    let a = tree[**a].uct(parent_visits, exploration);
+                    let b = tree[**b].uct(parent_visits, exploration);
+                    a.partial_cmp(&b).unwrap()
+                })
+                .unwrap();
+            board.add_move(tree[node].player, tree[node].played.unwrap());
+        }
+
+        // Expansion: add a child for one untried move.
+        if !tree[node].untried.is_empty() {
+            let choice = self.rng.gen_range(0, tree[node].untried.len());
+            let position = tree[node].untried.swap_remove(choice);
+            let player = tree[node].player.opponent();
+            board.add_move(player, position);
+            tree.push(Node::new(Some(position), player, Some(node), &board));
+            let child = tree.len() - 1;
+            tree[node].children.push(child);
+            node = child;
+        }
+
+        // Simulation: play at random to the end of the game.
+        while let Some(player) = board.whose_turn() {
+            let empty_positions = board.empty_positions();
+            let choice = self.rng.gen_range(0, empty_positions.len());
+            board.add_move(player, empty_positions[choice]);
+        }
+        let result = board.get_game_result();
+
+        // Backpropagation.
+        let mut current = Some(node);
+        while let Some(index) = current {
+            let node = &mut tree[index];
+            node.visits += 1;
+            node.score += match result {
+                GameResult::Win(winner) if winner == node.player => 1.0,
+                GameResult::Draw => 0.5,
+                _ => 0.0,
+            };
+            current = node.parent;
+        }
+    }
+}
+
+impl<R: RngCore> Player for MctsPlayer<R> {
+    fn take_turn(&mut self, board: &Board) -> Position {
+        self.search(board)
+    }
+}
+
+#[cfg(test)]
+mod mcts_tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::strategies::RandomPlayer;
+
+    #[test]
+    fn test_never_loses_against_random() {
+        const NUM_GAMES: u64 = 30;
+        let mut mcts = MctsPlayer::with_seed(Budget::Iterations(1000), 1);
+        for seed in 0..NUM_GAMES {
+            let game = Game::new(&mut mcts, RandomPlayer::with_seed(seed));
+            assert_ne!(game.result(), GameResult::Win(Token::O), "{}", game.board());
+
+            let game = Game::new(RandomPlayer::with_seed(seed), &mut mcts);
+            assert_ne!(game.result(), GameResult::Win(Token::X), "{}", game.board());
+        }
+    }
+
+    #[test]
+    fn test_wins_and_blocks() {
+        let mut player = MctsPlayer::with_seed(Budget::Iterations(1000), 4);
+        let board = Board::from_string(
+            "XX-\
+             OO-\
+             ---",
+        );
+        assert_eq!(player.take_turn(&board), Position::new(0, 2));
+
+        let board = Board::from_string(
+            "XX-\
+             O--\
+             ---",
+        );
+        assert_eq!(player.take_turn(&board), Position::new(0, 2));
+    }
+
+    #[test]
+    fn test_same_seed_same_moves() {
+        let board = Board::new();
+        let mut first = MctsPlayer::with_seed(Budget::Iterations(100), 5);
+        let mut second = MctsPlayer::with_seed(Budget::Iterations(100), 5);
+        for _ in 0..5 {
+            assert_eq!(first.take_turn(&board), second.take_turn(&board));
+        }
+    }
+
+    #[test]
+    fn test_time_budget() {
+        let mut player = MctsPlayer::with_seed(Budget::Time(Duration::from_millis(20)), 3);
+        assert_eq!(player.budget(), Budget::Time(Duration::from_millis(20)));
+        let board = Board::new();
+        assert!(board.contains(player.take_turn(&board)));
+        assert_gt!(player.last_iterations(), 0);
+    }
+
+    #[test]
+    fn test_larger_board_and_misere() {
+        let mut player = MctsPlayer::with_seed(Budget::Iterations(500), 4);
+        let board = Board::parse_with_run_length(
+            "XXX--\
+             OOO--\
+             -----\
+             -----\
+             -----",
+            4,
+        )
+        .unwrap();
+        assert_eq!(player.take_turn(&board), Position::new(0, 3));
+
+        let mut board = Board::from_string(
+            "XX-\
+             OO-\
+             ---",
+        );
+        board.set_rules(crate::board::Rules::Misere);
+        assert_ne!(player.take_turn(&board), Position::new(0, 2));
+    }
+
+    #[test]
+    fn test_exploration() {
+        let mut player = MctsPlayer::with_seed(Budget::Iterations(10), 0);
+        assert_eq!(player.exploration(), DEFAULT_EXPLORATION);
+        player.set_exploration(0.5);
+        assert_eq!(player.exploration(), 0.5);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid exploration constant: -1")]
+    fn test_invalid_exploration() {
+        MctsPlayer::with_seed(Budget::Iterations(10), 0).set_exploration(-1.0);
+    }
+} // mod mcts_tests
@@ -0,0 +1,598 @@
+//! MENACE, Donald Michie's Machine Educable Noughts And Crosses Engine, from 1961.
+//!
+//! Michie built it from matchboxes, one for every position it could face, each holding
+//! coloured beads for the moves it could make there.  To move, it shakes out a bead at random,
+//! so moves with more beads are played more often.  When the game ends, every bead it played
+//! is rewarded with more of the same colour if it won or drew, and confiscated if it lost.
+//! As in Michie's design, positions that are rotations or reflections of each other
+//! share a matchbox.
+use crate::board::{Board, GameResult, Position, Rules, Token};
+use crate::game::Game;
+use crate::learning::{EpochResult, TrainingReport};
+use crate::player::Player;
+use crate::saved::{self, Format, LoadError};
+
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+/// The number of squares on the board MENACE plays.
+const SQUARES: usize = 9;
+
+/// The format of saved matchboxes.
+const FORMAT: Format = Format {
+    header: "tictactoe matchboxes",
+    version: 1,
+    name: "matchbox",
+};
+
+/// The beads for each square of a matchbox's position, row by row.
+type Beads = [u32; SQUARES];
+
+/// How many beads are added to, or taken from, each matchbox played at the end of a game.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Reinforcement {
+    /// Beads added for each move of a game that was won.
+    pub win: u32,
+    /// Beads added for each move of a game that was drawn.
+    pub draw: u32,
+    /// Beads taken away for each move of a game that was lost.
+    pub loss: u32,
+}
+
+impl Reinforcement {
+    /// Michie's own choice: three beads for a win, one for a draw, and the loss of the bead played.
+    pub const MICHIE: Reinforcement = Reinforcement {
+        win: 3,
+        draw: 1,
+        loss: 1,
+    };
+}
+
+impl Default for Reinforcement {
+    fn default() -> Reinforcement {
+        Reinforcement::MICHIE
+    }
+}
+
+/// The contents of one matchbox, as seen from a particular board.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Matchbox {
+    board: Board,
+    beads: Vec<(Position, u32)>,
+}
+
+impl Matchbox {
+    /// Returns the number of beads for each empty square, in board order.
+    pub fn beads(&self) -> &[(Position, u32)] {
+        &self.beads
+    }
+
+    /// Returns the number of beads in the matchbox.
+    pub fn total(&self) -> u32 {
+        self.beads.iter().map(|(_position, beads)| beads).sum()
+    }
+}
+
+impl std::fmt::Display for Matchbox {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        // Will look something like:
+        //  X | 3 | 3
+        // ---+---+---
+        //  3 | O | 3
+        // ---+---+---
+        //  3 | 3 | 3
+        // Beads: 21
+        for row in 0..self.board.rows() {
+            if row > 0 {
+                writeln!(f, "---+---+---")?;
+            }
+            let squares: Vec<String> = (0..self.board.columns())
+                .map(|column| {
+                    let position = Position::new(row, column);
+                    match self.board.token_at(position) {
+                        Some(token) => format!(" {} ", token),
+                        None => {
+                            let (_position, beads) = self
+                                .beads
+                                .iter()
+                                .find(|(empty, _beads)| *empty == position)
+                                .unwrap();
+                            format!("{:^3}", beads)
+                        }
+                    }
+                })
+                .collect();
+            writeln!(f, "{}", squares.join("|"))?;
+        }
+        writeln!(f, "Beads: {}", self.total())
+    }
+}
+
+/// A player that learns like MENACE.
+///
+/// It plays the standard 3x3 game, as either X or O.  After each game, call learn() with the
+/// result to reinforce the moves it made, or train it with train_against().
+/// A matchbox that has run out of beads is played at random.
+///
+/// # Examples
+/// ```
+/// use tictactoelib::board::{Board, Position};
+/// use tictactoelib::game::Game;
+/// use tictactoelib::menace::MenacePlayer;
+/// use tictactoelib::strategies::RandomPlayer;
+///
+/// let mut menace = MenacePlayer::with_seed(1);
+/// // Before it has played, every first move has four beads.
+/// assert_eq!(menace.matchbox(&Board::new()).beads()[0], (Position::new(0, 0), 4));
+///
+/// let mut game = Game::start(&mut menace, RandomPlayer::with_seed(2));
+/// let result = game.play_to_end();
+/// menace.learn(result);
+/// ```
+pub struct MenacePlayer<R: RngCore = StdRng> {
+    /// The matchboxes, keyed by the canonical form of their position.
+    matchboxes: HashMap<Board, Beads>,
+    reinforcement: Reinforcement,
+    rng: R,
+    /// The matchboxes opened in the current game, and the square of the bead drawn from each.
+    played: Vec<(Board, usize)>,
+}
+
+impl MenacePlayer {
+    /// Creates an untrained player seeded from the operating system's entropy source.
+    pub fn new() -> MenacePlayer {
+        MenacePlayer::with_rng(StdRng::from_entropy())
+    }
+
+    /// Creates an untrained player whose moves are reproducible for a given seed.
+    pub fn with_seed(seed: u64) -> MenacePlayer {
+        MenacePlayer::with_rng(StdRng::seed_from_u64(seed))
+    }
+}
+
+impl Default for MenacePlayer {
+    fn default() -> MenacePlayer {
+        MenacePlayer::new()
+    }
+}
+
+impl<R: RngCore> MenacePlayer<R> {
+    /// Creates an untrained player that draws its beads with the given random number generator.
+    pub fn with_rng(rng: R) -> MenacePlayer<R> {
+        MenacePlayer {
+            matchboxes: HashMap::new(),
+            reinforcement: Reinforcement::MICHIE,
+            rng,
+            played: Vec::new(),
+        }
+    }
+
+    /// Returns how the player is rewarded and punished.
+    pub fn reinforcement(&self) -> Reinforcement {
+        self.reinforcement
+    }
+
+    /// Changes how the player is rewarded and punished.
+    pub fn set_reinforcement(&mut self, reinforcement: Reinforcement) {
+        self.reinforcement = reinforcement;
+    }
+
+    /// Returns the number of matchboxes the player has opened.
+    pub fn len(&self) -> usize {
+        self.matchboxes.len()
+    }
+
+    /// Returns whether the player has not opened any matchboxes yet.
+    pub fn is_empty(&self) -> bool {
+        self.matchboxes.is_empty()
+    }
+
+    /// Returns the contents of the matchbox for the board, turned to match the board.
+    ///
+    /// # Examples
+    /// ```
+    /// use tictactoelib::board::Board;
+    /// use tictactoelib::menace::MenacePlayer;
+    ///
+    /// let menace = MenacePlayer::with_seed(0);
+    /// let board: Board = "X--/-O-/---".parse().unwrap();
+    /// assert_eq!(
+    ///     menace.matchbox(&board).to_string(),
+    ///     " X | 3 | 3 \n---+---+---\n 3 | O | 3 \n---+---+---\n 3 | 3 | 3 \nBeads: 21\n"
+    /// );
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the board is not the standard 3x3 game.
+    pub fn matchbox(&self, board: &Board) -> Matchbox {
+        check_board(board);
+        let (canonical, transform) = board.canonical();
+        let beads = self.beads(&canonical);
+        Matchbox {
+            board: *board,
+            beads: board
+                .empty_positions_iter()
+                .map(|position| (position, beads[index_of(transform.apply(position, board))]))
+                .collect(),
+        }
+    }
+
+    /// Rewards or punishes every move the player made in the game that just ended,
+    /// and gets ready for the next game.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the game is not over.
+    pub fn learn(&mut self, result: GameResult) {
+        if result == GameResult::InProgress {
+            panic!("Game should be over!");
+        }
+        let played = std::mem::take(&mut self.played);
+        let token = match played.first() {
+            Some((board, _square)) => board.whose_turn().unwrap(),
+            None => return,
+        };
+        let reinforcement = self.reinforcement;
+        for (board, square) in played {
+            let beads = &mut self.matchboxes.get_mut(&board).unwrap()[square];
+            match result {
+                GameResult::Win(winner) if winner == token => *beads += reinforcement.win,
+                GameResult::Win(_winner) => *beads = beads.saturating_sub(reinforcement.loss),
+                _ => *beads += reinforcement.draw,
+            }
+        }
+    }
+
+    /// Plays training games against the opponent, with the player taking X and O in turn,
+    /// learning from each, and returns how the games of each epoch ended.
+    pub fn train_against(
+        &mut self,
+        opponent: &mut impl Player,
+        epochs: u32,
+        games_per_epoch: u32,
+    ) -> TrainingReport {
+        let mut report = TrainingReport::default();
+        for _epoch in 0..epochs {
+            let mut epoch = EpochResult::default();
+            for game in 0..games_per_epoch {
+                let (token, result) = if game % 2 == 0 {
+                    (Token::X, Game::new(&mut *self, &mut *opponent).result())
+                } else {
+                    (Token::O, Game::new(&mut *opponent, &mut *self).result())
+                };
+                epoch.add(&result, token);
+                self.learn(result);
+            }
+            report.push(epoch);
+        }
+        report
+    }
+
+    /// Returns the beads of the matchbox for a canonical board, opened or not.
+    fn beads(&self, canonical: &Board) -> Beads {
+        match self.matchboxes.get(canonical) {
+            Some(beads) => *beads,
+            None => initial_beads(canonical),
+        }
+    }
+
+    /// Writes the matchboxes as text: a header line, then one matchbox per line,
+    /// with its position and the beads for each empty square.
+    pub fn write_to(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        let lines = self
+            .matchboxes
+            .iter()
+            .map(|(board, beads)| {
+                let mut line = board.to_compact_string();
+                for position in board.empty_positions_iter() {
+                    line.push_str(&format!(" {}", beads[index_of(position)]));
+                }
+                line
+            })
+            .collect();
+        FORMAT.write(writer, lines)
+    }
+
+    /// Replaces the matchboxes with those written by write_to().
+    pub fn read_from(&mut self, reader: &mut impl BufRead) -> Result<(), LoadError> {
+        let matchboxes = FORMAT.read(reader, |line| {
+            let mut words = line.split_whitespace();
+            let board = words
+                .next()
+                .and_then(|board| board.parse::<Board>().ok())
+                .filter(|board| {
+                    board.rows() == 3 && board.columns() == 3 && board.whose_turn().is_some()
+                })?;
+            let (canonical, transform) = board.canonical();
+            let mut beads = [0; SQUARES];
+            for position in board.empty_positions_iter() {
+                let count = words.next().and_then(|count| count.parse::<u32>().ok())?;
+                beads[index_of(transform.apply(position, &board))] = count;
+            }
+            match words.next() {
+                None => Some((canonical, beads)),
+                Some(_extra) => None,
+            }
+        })?;
+        self.matchboxes = matchboxes.into_iter().collect();
+        self.played.clear();
+        Ok(())
+    }
+
+    /// Saves the matchboxes to a file.
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        saved::save(path, |file| self.write_to(file))
+    }
+
+    /// Replaces the matchboxes with those saved by save().
+    pub fn load(&mut self, path: impl AsRef<Path>) -> Result<(), LoadError> {
+        saved::load(path, |file| self.read_from(file))
+    }
+}
+
+impl<R: RngCore> Player for MenacePlayer<R> {
+    /// # Panics
+    ///
+    /// Panics if the board is not the standard 3x3 game.
+    fn take_turn(&mut self, board: &Board) -> Position {
+        check_board(board);
+        // A board with no more moves than the last one, or a change of side, means a new game,
+        // so the moves of the last game are forgotten if learn() was not called.
+        if let Some((last, _square)) = self.played.last() {
+            let moves = |board: &Board| SQUARES - board.empty_positions_iter().count();
+            if moves(board) <= moves(last) || board.whose_turn() != last.whose_turn() {
+                self.played.clear();
+            }
+        }
+
+        let (canonical, transform) = board.canonical();
+        let beads = self.beads(&canonical);
+        let total: u32 = beads.iter().sum();
+        let square = if total == 0 {
+            let empty_positions = canonical.empty_positions();
+            index_of(empty_positions[self.rng.gen_range(0, empty_positions.len())])
+        } else {
+            let mut bead = self.rng.gen_range(0, total);
+            let mut square = 0;
+            while bead >= beads[square] {
+                bead -= beads[square];
+                square += 1;
+            }
+            square
+        };
+        self.matchboxes.entry(canonical).or_insert(beads);
+        self.played.push((canonical, square));
+        transform.invert(position_of(square), board)
+    }
+}
+
+/// Returns the beads Michie put in a new matchbox: four for each of the first move's squares,
+/// three for the second, two for the third, and one thereafter.
+fn initial_beads(board: &Board) -> Beads {
+    let moves_made = SQUARES - board.empty_positions_iter().count();
+    let count = 4u32.saturating_sub(moves_made as u32 / 2).max(1);
+    let mut beads = [0; SQUARES];
+    for position in board.empty_positions_iter() {
+        beads[index_of(position)] = count;
+    }
+    beads
+}
+
+fn check_board(board: &Board) {
+    if board.rows() != 3
+        || board.columns() != 3
+        || board.run_length() != 3
+        || board.rules() != Rules::Standard
+    {
+        panic!("MENACE only plays the standard 3x3 game!");
+    }
+}
+
+fn index_of(position: Position) -> usize {
+    position.row() as usize * 3 + position.column() as usize
+}
+
+fn position_of(square: usize) -> Position {
+    Position::new(square as u8 / 3, square as u8 % 3)
+}
+
+#[cfg(test)]
+mod menace_tests {
+    use super::*;
+    use crate::optimal::OptimalPlayer;
+    use crate::strategies::RandomPlayer;
+
+    #[test]
+    fn test_learns_against_random() {
+        let mut menace = MenacePlayer::with_seed(1);
+        let report = menace.train_against(&mut RandomPlayer::with_seed(2), 5, 500);
+        let (first, last) = (report.epochs()[0], report.last().unwrap());
+        assert_lt!(last.loss_rate(), first.loss_rate());
+        assert_gt!(last.win_rate(), first.win_rate());
+        assert!(!menace.is_empty());
+    }
+
+    #[test]
+    fn test_learns_to_draw_against_optimal() {
+        let mut menace = MenacePlayer::with_seed(3);
+        let report = menace.train_against(&mut OptimalPlayer::new(), 10, 100);
+        let (first, last) = (report.epochs()[0], report.last().unwrap());
+        assert_gt!(last.draw_rate(), first.draw_rate());
+    }
+
+    #[test]
+    fn test_reinforcement() {
+        let mut menace = MenacePlayer::with_seed(4);
+        let board = Board::new();
+        let position = menace.take_turn(&board);
+        menace.learn(GameResult::Win(Token::X));
+        let beads = |menace: &MenacePlayer, position| {
+            let matchbox = menace.matchbox(&board);
+            matchbox
+                .beads()
+                .iter()
+                .find(|(square, _beads)| *square == position)
+                .unwrap()
+                .1
+        };
+        assert_eq!(beads(&menace, position), 4 + 3);
+        assert_eq!(menace.matchbox(&board).total(), 4 * 9 + 3);
+
+        for (result, change) in [(GameResult::Draw, 1), (GameResult::Win(Token::O), -1)] {
+            let position = menace.take_turn(&board);
+            let before = beads(&menace, position) as i32;
+            menace.learn(result);
+            assert_eq!(beads(&menace, position) as i32, before + change);
+        }
+    }
+
+    #[test]
+    fn test_draws_beads_in_proportion() {
+        let mut menace = MenacePlayer::with_seed(5);
+        // Only the top right corner has any beads, and this board is the saved one turned over.
+        let saved = "tictactoe matchboxes 1\nX---O---- 0 9 0 0 0 0 0\n";
+        menace.read_from(&mut saved.as_bytes()).unwrap();
+        let board = Board::from_string(
+            "---\
+             -O-\
+             --X",
+        );
+        let matchbox = menace.matchbox(&board);
+        assert_eq!(matchbox.total(), 9);
+        for _ in 0..10 {
+            assert_eq!(menace.take_turn(&board), Position::new(2, 0));
+        }
+    }
+
+    #[test]
+    fn test_empty_matchbox() {
+        let mut menace = MenacePlayer::with_seed(6);
+        let saved = "tictactoe matchboxes 1\n--------- 0 0 0 0 0 0 0 0 0\n";
+        menace.read_from(&mut saved.as_bytes()).unwrap();
+        let board = Board::new();
+        for _ in 0..10 {
+            assert!(board.is_position_unused(menace.take_turn(&board)));
+        }
+    }
+
+    #[test]
+    fn test_new_game_without_learning() {
+        let mut menace = MenacePlayer::with_seed(7);
+        let mut board = Board::new();
+        board.add_move(Token::X, menace.take_turn(&board));
+        board.add_move(Token::O, board.empty_positions()[0]);
+        menace.take_turn(&board);
+        // The first game is abandoned, and only the second is reinforced.
+        let position = menace.take_turn(&Board::new());
+        menace.learn(GameResult::Win(Token::X));
+        let beads: Vec<u32> = menace
+            .matchbox(&Board::new())
+            .beads()
+            .iter()
+            .filter(|(square, _beads)| *square != position)
+            .map(|(_square, beads)| *beads)
+            .collect();
+        assert!(beads.iter().all(|beads| *beads == 4));
+        assert_eq!(menace.matchbox(&board).total(), 3 * 7);
+    }
+
+    #[test]
+    fn test_switching_sides_without_learning() {
+        let mut menace = MenacePlayer::with_seed(8);
+        // MENACE opens a game as X, which is abandoned...
+        menace.take_turn(&Board::new());
+        // ...and then plays O in the next game, which it wins.
+        let mut board = Board::new();
+        board.add_move(Token::X, Position::new(1, 1));
+        let position = menace.take_turn(&board);
+        menace.learn(GameResult::Win(Token::O));
+
+        assert!(menace
+            .matchbox(&Board::new())
+            .beads()
+            .iter()
+            .all(|(_square, beads)| *beads == 4));
+        let matchbox = menace.matchbox(&board);
+        assert!(matchbox.beads().contains(&(position, 4 + 3)));
+        assert_eq!(matchbox.total(), 4 * 8 + 3);
+    }
+
+    #[test]
+    fn test_matchbox_display() {
+        let menace = MenacePlayer::with_seed(0);
+        assert_eq!(
+            menace.matchbox(&Board::new()).to_string(),
+            " 4 | 4 | 4 \n---+---+---\n 4 | 4 | 4 \n---+---+---\n 4 | 4 | 4 \nBeads: 36\n"
+        );
+        let board = Board::from_string(
+            "XOX\
+             OXO\
+             ---",
+        );
+        assert_eq!(
+            menace.matchbox(&board).to_string(),
+            " X | O | X \n---+---+---\n O | X | O \n---+---+---\n 1 | 1 | 1 \nBeads: 3\n"
+        );
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let mut menace = MenacePlayer::with_seed(8);
+        menace.train_against(&mut RandomPlayer::with_seed(9), 1, 50);
+        let mut saved = Vec::new();
+        menace.write_to(&mut saved).unwrap();
+
+        let mut loaded = MenacePlayer::with_seed(10);
+        loaded.read_from(&mut saved.as_slice()).unwrap();
+        assert_eq!(loaded.matchboxes, menace.matchboxes);
+
+        let path = std::env::temp_dir().join("tictactoe_menace_test.matchboxes");
+        menace.save(&path).unwrap();
+        let mut loaded = MenacePlayer::with_seed(11);
+        loaded.load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.matchboxes, menace.matchboxes);
+    }
+
+    #[test]
+    fn test_load_errors() {
+        let mut menace = MenacePlayer::with_seed(0);
+        let read = |menace: &mut MenacePlayer, text: &str| {
+            menace
+                .read_from(&mut text.as_bytes())
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(read(&mut menace, ""), "Not a matchbox file");
+        assert_eq!(
+            read(&mut menace, "tictactoe values 1"),
+            "Not a matchbox file"
+        );
+        assert_eq!(
+            read(&mut menace, "tictactoe matchboxes 2\n"),
+            "Unsupported matchbox version 2"
+        );
+        assert_eq!(
+            read(
+                &mut menace,
+                "tictactoe matchboxes 1\nX---O---- 1 1 1 1 1 1\n"
+            ),
+            "Invalid matchbox line 2"
+        );
+        assert_eq!(
+            read(&mut menace, "tictactoe matchboxes 1\nXXXOO---- 1 1 1 1\n"),
+            "Invalid matchbox line 2"
+        );
+        assert!(menace.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "MENACE only plays the standard 3x3 game!")]
+    fn test_larger_board() {
+        MenacePlayer::with_seed(0).take_turn(&Board::with_size(4, 4, 3));
+    }
+} // mod menace_tests
@@ -1,11 +1,74 @@
 //! An optimal player, who never loses!
-use crate::board::{Board, GameResult, Position};
+use crate::board::{Board, GameResult, Position, Rules};
 use crate::player::Player;
+
 #[cfg(test)]
 use crate::strategies::RandomPlayer;
+use std::collections::HashMap;
+
+/// The value of a position or move, from the point of view of the player making the choice.
+///
+/// Each outcome records the number of moves (plies) until the game ends, with best play.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Evaluation {
+    /// The player wins, after the given number of moves.
+    Win(u16),
+    /// The game is drawn, after the given number of moves.
+    Draw(u16),
+    /// The player loses, after the given number of moves.
+    Loss(u16),
+}
+
+impl Evaluation {
+    /// Returns a score where higher is better:
+    /// fast wins beat slow wins, which beat draws, which beat slow losses, which beat fast losses.
+    pub fn score(self) -> i32 {
+        match self {
+            Evaluation::Win(plies) => 1000 - plies as i32,
+            Evaluation::Draw(_plies) => 0,
+            Evaluation::Loss(plies) => plies as i32 - 1000,
+        }
+    }
+
+    /// Returns the number of moves until the game ends.
+    pub fn plies(self) -> u16 {
+        match self {
+            Evaluation::Win(plies) | Evaluation::Draw(plies) | Evaluation::Loss(plies) => plies,
+        }
+    }
+
+    /// Converts an evaluation for the opponent, after a move,
+    /// into the evaluation of that move for the player who made it.
+    pub(crate) fn for_previous_player(self) -> Evaluation {
+        match self {
+            Evaluation::Win(plies) => Evaluation::Loss(plies + 1),
+            Evaluation::Draw(plies) => Evaluation::Draw(plies + 1),
+            Evaluation::Loss(plies) => Evaluation::Win(plies + 1),
+        }
+    }
+}
+
+impl std::fmt::Display for Evaluation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let (outcome, plies) = match self {
+            Evaluation::Win(plies) => ("win", plies),
+            Evaluation::Draw(plies) => ("draw", plies),
+            Evaluation::Loss(plies) => ("loss", plies),
+        };
+        let unit = if *plies == 1 { "move" } else { "moves" };
+        write!(f, "{} in {} {}", outcome, plies, unit)
+    }
+}
 
 /// The OptimalPlayer never loses a game.
-pub struct OptimalPlayer {}
+///
+/// It prefers the fastest win, and when it cannot avoid losing, the slowest loss.
+/// Positions it has already solved are remembered, so later turns are nearly free.
+#[derive(Default)]
+pub struct OptimalPlayer {
+    /// Solved positions, keyed by their canonical form, from the point of view of the player to move.
+    table: HashMap<Board, Evaluation>,
+}
 
 impl Player for OptimalPlayer {
     fn take_turn(&mut self, board: &Board) -> Position {
@@ -14,46 +77,80 @@ impl Player for OptimalPlayer {
 }
 
 impl OptimalPlayer {
-    fn get_best_move(&self, board: &Board) -> Position {
-        // To speed things up, first check if the center is still available.
-        let center = Position::new(1, 1);
-        if board.is_position_unused(center) {
-            return center;
+    /// Creates an optimal player, with nothing solved yet.
+    pub fn new() -> OptimalPlayer {
+        OptimalPlayer {
+            table: HashMap::new(),
         }
+    }
 
-        let who_am_i = board.whose_turn().unwrap();
-
-        let mut positions_and_results = Vec::<(Position, GameResult)>::with_capacity(8);
+    /// Returns every legal move on the board, with its value for the player whose turn it is.
+    ///
+    /// Examples
+    /// ```
+    /// use tictactoelib::board::{Board, Position};
+    /// use tictactoelib::optimal::{Evaluation, OptimalPlayer};
+    /// let board: Board = "XX-OO----".parse().unwrap();
+    /// let mut player = OptimalPlayer::new();
+    /// let evaluations = player.evaluate_moves(&board);
+    /// assert!(evaluations.contains(&(Position::new(0, 2), Evaluation::Win(1))));
+    /// assert!(evaluations.contains(&(Position::new(2, 2), Evaluation::Loss(2))));
+    /// ```
+    pub fn evaluate_moves(&mut self, board: &Board) -> Vec<(Position, Evaluation)> {
+        let who_am_i = match board.whose_turn() {
+            Some(token) => token,
+            None => return Vec::new(),
+        };
+        board
+            .empty_positions()
+            .into_iter()
+            .map(|potential_move| {
+                let mut next_board = *board;
+                next_board.add_move(who_am_i, potential_move);
+                (
+                    potential_move,
+                    self.evaluate(&next_board).for_previous_player(),
+                )
+            })
+            .collect()
+    }
 
-        for potential_move in board.empty_positions() {
-            let mut next_board = *board;
-            next_board.add_move(who_am_i, potential_move);
-            let result = self.get_eventual_game_result(&next_board);
-            if result == GameResult::Win(who_am_i) {
-                return potential_move;
-            }
-            positions_and_results.push((potential_move, result));
+    /// Returns the value of the board for the player whose turn it is,
+    /// or for the player who would have moved next, if the game is over.
+    pub fn evaluate(&mut self, board: &Board) -> Evaluation {
+        match board.get_game_result() {
+            // The previous player completed a line, and won or lost by it.
+            GameResult::Win(_winner) => match board.rules() {
+                Rules::Standard => return Evaluation::Loss(0),
+                Rules::Misere => return Evaluation::Win(0),
+            },
+            GameResult::Draw => return Evaluation::Draw(0),
+            GameResult::InProgress => {}
         }
 
-        for (potential_move, result) in positions_and_results.iter() {
-            if *result == GameResult::Draw {
-                return *potential_move;
-            }
+        let (key, _transform) = board.canonical();
+        if let Some(evaluation) = self.table.get(&key) {
+            return *evaluation;
         }
-
-        positions_and_results[0].0
+        let evaluation = self
+            .evaluate_moves(board)
+            .into_iter()
+            .map(|(_position, evaluation)| evaluation)
+            .max_by_key(|evaluation| evaluation.score())
+            .unwrap();
+        self.table.insert(key, evaluation);
+        evaluation
     }
 
-    fn get_eventual_game_result(&self, board: &Board) -> GameResult {
-        let result = board.get_game_result();
-        if result != GameResult::InProgress {
-            result
-        } else {
-            let mut next_board = *board;
-            let best_move = self.get_best_move(&next_board);
-            next_board.add_move(next_board.whose_turn().unwrap(), best_move);
-            self.get_eventual_game_result(&next_board)
+    fn get_best_move(&mut self, board: &Board) -> Position {
+        let mut best: Option<(Position, Evaluation)> = None;
+        for (position, evaluation) in self.evaluate_moves(board) {
+            // Keep the first of equally good moves.
+            if best.map_or(true, |(_position, best)| evaluation.score() > best.score()) {
+                best = Some((position, evaluation));
+            }
         }
+        best.expect("There are no moves to make!").0
     }
 }
 
@@ -65,8 +162,8 @@ mod tests {
 
     #[test]
     fn test_draws_against_itself() {
-        let x = OptimalPlayer {};
-        let o = OptimalPlayer {};
+        let x = OptimalPlayer::new();
+        let o = OptimalPlayer::new();
         let game = Game::new(x, o);
         assert_eq!(game.result(), GameResult::Draw);
     }
@@ -74,22 +171,58 @@ mod tests {
     #[test]
     fn test_never_loses() {
         const NUM_GAMES: u32 = 50;
-        for _i in 0..NUM_GAMES {
-            let x = OptimalPlayer {};
-            let o = RandomPlayer {};
+        let mut optimal = OptimalPlayer::new();
+        for seed in 0..NUM_GAMES as u64 {
+            let x = &mut optimal;
+            let o = RandomPlayer::with_seed(seed);
             let game = Game::new(x, o);
             assert_ne!(game.result(), GameResult::Win(Token::O));
 
-            let x = RandomPlayer {};
-            let o = OptimalPlayer {};
+            let x = RandomPlayer::with_seed(seed);
+            let o = &mut optimal;
             let game = Game::new(x, o);
             assert_ne!(game.result(), GameResult::Win(Token::X));
         }
     }
 
+    #[test]
+    fn test_never_loses_against_any_opponent() {
+        let verification = crate::verification::verify(OptimalPlayer::new());
+        assert!(verification.never_loses(), "{}", verification);
+        assert_gt!(verification.games_as_o(), verification.games_as_x());
+    }
+
+    #[test]
+    fn test_misere_draws_against_itself() {
+        let x = OptimalPlayer::new();
+        let o = OptimalPlayer::new();
+        let mut game = Game::start_from(Board::misere(), x, o);
+        assert_eq!(game.play_to_end(), GameResult::Draw);
+    }
+
+    #[test]
+    fn test_misere_never_loses_against_any_opponent() {
+        let verification = crate::verification::verify_from(OptimalPlayer::new(), Board::misere());
+        assert!(verification.never_loses(), "{}", verification);
+    }
+
+    #[test]
+    fn test_misere_avoids_completing_a_line() {
+        let mut player = OptimalPlayer::new();
+        let mut board = Board::from_string(
+            "XX-\
+             OO-\
+             ---",
+        );
+        board.set_rules(Rules::Misere);
+        let evaluations = player.evaluate_moves(&board);
+        assert!(evaluations.contains(&(Position::new(0, 2), Evaluation::Loss(1))));
+        assert_ne!(player.take_turn(&board), Position::new(0, 2));
+    }
+
     #[test]
     fn test_single_move_left() {
-        let mut player = OptimalPlayer {};
+        let mut player = OptimalPlayer::new();
         let board = Board::from_string(
             "XOX\
              OO-\
@@ -100,7 +233,7 @@ mod tests {
 
     #[test]
     fn test_winning_move() {
-        let mut player = OptimalPlayer {};
+        let mut player = OptimalPlayer::new();
 
         println!("Scenario #1");
         let board = Board::from_string(
@@ -127,9 +260,71 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_prefers_fastest_win() {
+        let mut player = OptimalPlayer::new();
+        // X can win at once on the top row, or later with a fork in the corner.
+        let board = Board::from_string(
+            "XX-\
+             -O-\
+             --O",
+        );
+        let evaluations = player.evaluate_moves(&board);
+        assert!(evaluations.contains(&(Position::new(0, 2), Evaluation::Win(1))));
+        assert!(evaluations.contains(&(Position::new(2, 0), Evaluation::Win(3))));
+        assert_eq!(player.take_turn(&board), Position::new(0, 2));
+    }
+
+    #[test]
+    fn test_prefers_slowest_loss() {
+        let mut player = OptimalPlayer::new();
+        // O cannot avoid losing, but blocking the middle row makes X take longer.
+        let board = Board::from_string(
+            "--X\
+             X--\
+             OOX",
+        );
+        assert_eq!(
+            player.evaluate_moves(&board),
+            vec![
+                (Position::new(0, 0), Evaluation::Loss(2)),
+                (Position::new(0, 1), Evaluation::Loss(2)),
+                (Position::new(1, 1), Evaluation::Loss(2)),
+                (Position::new(1, 2), Evaluation::Loss(4)),
+            ]
+        );
+        assert_eq!(player.take_turn(&board), Position::new(1, 2));
+    }
+
+    #[test]
+    fn test_evaluate_moves_covers_every_move() {
+        let mut player = OptimalPlayer::new();
+        let evaluations = player.evaluate_moves(&Board::new());
+        assert_eq!(evaluations.len(), 9);
+        for (_position, evaluation) in evaluations {
+            assert_eq!(evaluation, Evaluation::Draw(9));
+        }
+        assert_eq!(player.evaluate(&Board::new()), Evaluation::Draw(9));
+
+        let finished = Board::from_string(
+            "XXX\
+             OO-\
+             ---",
+        );
+        assert_eq!(player.evaluate_moves(&finished), Vec::new());
+        assert_eq!(player.evaluate(&finished), Evaluation::Loss(0));
+    }
+
+    #[test]
+    fn test_evaluation_display() {
+        assert_eq!(Evaluation::Win(1).to_string(), "win in 1 move");
+        assert_eq!(Evaluation::Draw(9).to_string(), "draw in 9 moves");
+        assert_eq!(Evaluation::Loss(4).to_string(), "loss in 4 moves");
+    }
+
     #[test]
     fn test_prevent_loss() {
-        let mut player = OptimalPlayer {};
+        let mut player = OptimalPlayer::new();
         let board = Board::from_string(
             "XOO\
              OX-\
@@ -7,3 +7,18 @@ pub trait Player {
     /// given an in-progress game Board.
     fn take_turn(&mut self, board: &Board) -> Position;
 }
+
+/// A borrowed player can play too, so that one player (and anything it has learned)
+/// can be used for several games.
+impl<P: Player + ?Sized> Player for &mut P {
+    fn take_turn(&mut self, board: &Board) -> Position {
+        (**self).take_turn(board)
+    }
+}
+
+/// A boxed player can play too, so that the kind of player can be chosen at runtime.
+impl<P: Player + ?Sized> Player for Box<P> {
+    fn take_turn(&mut self, board: &Board) -> Position {
+        (**self).take_turn(board)
+    }
+}
@@ -0,0 +1,621 @@
+//! A text format for saving, loading and replaying games.
+//!
+//! A record starts with header tags, one per line, followed by the numbered moves:
+//!
+//! ```text
+//! [X "Alice"]
+//! [O "OptimalPlayer"]
+//! [Date "2020-04-01"]
+//! [Variant "3x3/3"]
+//! [Result "Draw"]
+//!
+//! 1. b2 a1
+//! 2. c1 a3
+//! 3. a2 c2
+//! 4. b1 b3
+//! 5. c3
+//! ```
+//!
+//! The Variant tag gives the board's rows, columns and run length ("ROWSxCOLUMNS/RUN"),
+//! and defaults to the standard 3x3 board if it is missing.
+//! A Rules tag of "misere" records a game where completing a line loses;
+//! without it, the standard rules apply.
+//! The Result tag is one of "X", "O", "Draw" or "*" for a game still in progress.
+//! A Position tag, in the compact form of Board::to_compact_string(),
+//! records a game that did not start from an empty board.
+//!
+//! Moves are written as a column letter and a row number ("a1" is the top left),
+//! but numbers counting row by row from the top left (1-9 on the standard board)
+//! are also accepted, like the HumanPlayer uses.
+use crate::board::{Board, GameResult, MoveError, ParseBoardError, Position, Rules, Token};
+
+/// A complete record of a game: its header tags, starting board and moves.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameRecord {
+    tags: Vec<(String, String)>,
+    start: Board,
+    moves: Vec<Position>,
+}
+
+impl GameRecord {
+    /// Creates a record of the moves recorded on the given board,
+    /// with the Variant, Result and (if needed) Rules and Position tags filled in.
+    ///
+    /// Examples
+    /// ```
+    /// use tictactoelib::board::{Board, Position, Token};
+    /// use tictactoelib::record::GameRecord;
+    /// let mut board = Board::new();
+    /// board.add_move(Token::X, Position::new(1, 1));
+    /// let mut record = GameRecord::from_board(&board);
+    /// record.set_tag("X", "Alice");
+    /// assert_eq!(
+    ///     record.to_string(),
+    ///     "[Variant \"3x3/3\"]\n[Result \"*\"]\n[X \"Alice\"]\n\n1. b2\n"
+    /// );
+    /// ```
+    pub fn from_board(board: &Board) -> GameRecord {
+        let mut start = *board;
+        while start.undo_move().is_some() {}
+
+        let mut record = GameRecord {
+            tags: Vec::new(),
+            start,
+            moves: board.moves(),
+        };
+        record.set_tag(
+            "Variant",
+            &format!(
+                "{}x{}/{}",
+                board.rows(),
+                board.columns(),
+                board.run_length()
+            ),
+        );
+        if board.rules() == Rules::Misere {
+            record.set_tag("Rules", "misere");
+        }
+        let mut empty = Board::with_size(board.rows(), board.columns(), board.run_length());
+        empty.set_rules(board.rules());
+        if start != empty {
+            record.set_tag("Position", &start.to_compact_string());
+        }
+        record.set_tag("Result", result_tag(&board.get_game_result()));
+        record
+    }
+
+    /// Returns the value of the given header tag, if it is present.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag_name, _value)| tag_name == name)
+            .map(|(_name, value)| value.as_str())
+    }
+
+    /// Sets the value of a header tag, replacing any existing value.
+    /// New tags are written after the existing ones.
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self
+            .tags
+            .iter_mut()
+            .find(|(tag_name, _value)| tag_name == name)
+        {
+            Some(tag) => tag.1 = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// Returns the header tags, in the order they are written.
+    pub fn tags(&self) -> &[(String, String)] {
+        &self.tags
+    }
+
+    /// Returns the board the game started from.
+    pub fn start(&self) -> &Board {
+        &self.start
+    }
+
+    /// Returns the moves of the game, in order.
+    pub fn moves(&self) -> &[Position] {
+        &self.moves
+    }
+
+    /// Returns the board after every move has been played.
+    pub fn final_board(&self) -> Board {
+        self.replay().last().unwrap_or(self.start)
+    }
+
+    /// Returns an iterator over the board after each move (ply) of the game.
+    ///
+    /// Examples
+    /// ```
+    /// use tictactoelib::board::{GameResult, Token};
+    /// use tictactoelib::record::GameRecord;
+    /// let record: GameRecord = "1. a1 b1 2. a2 b2 3. a3".parse().unwrap();
+    /// let boards: Vec<_> = record.replay().collect();
+    /// assert_eq!(boards.len(), 5);
+    /// assert_eq!(boards[0].to_compact_string(), "X--------");
+    /// assert_eq!(boards[4].get_game_result(), GameResult::Win(Token::X));
+    /// ```
+    pub fn replay(&self) -> Replay<'_> {
+        Replay {
+            board: self.start,
+            moves: self.moves.iter(),
+        }
+    }
+}
+
+/// An iterator over the boards of a replayed game, created by GameRecord::replay().
+pub struct Replay<'a> {
+    board: Board,
+    moves: std::slice::Iter<'a, Position>,
+}
+
+impl<'a> Iterator for Replay<'a> {
+    type Item = Board;
+
+    fn next(&mut self) -> Option<Board> {
+        let at = *self.moves.next()?;
+        // The moves were checked when the record was created.
+        self.board.add_move(self.board.whose_turn().unwrap(), at);
+        Some(self.board)
+    }
+}
+
+impl std::fmt::Display for GameRecord {
+    /// Writes the record in the format described in the module documentation.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (name, value) in self.tags.iter() {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            writeln!(f, "[{} \"{}\"]", name, value)?;
+        }
+        writeln!(f)?;
+
+        // Number the moves in pairs, like 1. X O, unless O moved first.
+        let first_player = self.start.whose_turn().unwrap_or(Token::X);
+        let mut move_number = 1;
+        for (ply, at) in self.moves.iter().enumerate() {
+            let player = if ply % 2 == 0 {
+                first_player
+            } else {
+                first_player.opponent()
+            };
+            if ply == 0 && player == Token::O {
+                write!(f, "{}. ...", move_number)?;
+            } else if player == Token::X {
+                if ply > 0 {
+                    writeln!(f)?;
+                    move_number += 1;
+                }
+                write!(f, "{}.", move_number)?;
+            }
+            write!(f, " {}", format_position(*at))?;
+        }
+        if !self.moves.is_empty() {
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for GameRecord {
+    type Err = RecordError;
+
+    /// Parses a record, checking every move against the rules of the board.
+    fn from_str(contents: &str) -> Result<GameRecord, RecordError> {
+        let mut tags = Vec::new();
+        let mut move_text = String::new();
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.starts_with('[') {
+                tags.push(parse_tag(line)?);
+            } else {
+                move_text.push_str(line);
+                move_text.push(' ');
+            }
+        }
+
+        let variant = tags
+            .iter()
+            .find(|(name, _value)| name == "Variant")
+            .map_or("3x3/3", |(_name, value)| value.as_str());
+        let empty = parse_variant(variant)?;
+        let rules = match tags.iter().find(|(name, _value)| name == "Rules") {
+            None => Rules::Standard,
+            Some((_name, rules)) if rules == "standard" => Rules::Standard,
+            Some((_name, rules)) if rules == "misere" => Rules::Misere,
+            Some((_name, rules)) => return Err(RecordError::InvalidRules(rules.clone())),
+        };
+        let mut start = match tags.iter().find(|(name, _value)| name == "Position") {
+            Some((_name, position)) => {
+                let start = Board::parse_with_run_length(position, empty.run_length())
+                    .map_err(RecordError::InvalidPosition)?;
+                if start.rows() != empty.rows() || start.columns() != empty.columns() {
+                    return Err(RecordError::InvalidVariant(variant.to_string()));
+                }
+                start
+            }
+            None => empty,
+        };
+        start.set_rules(rules);
+
+        let mut board = start;
+        let mut moves = Vec::new();
+        for word in move_text.split_whitespace() {
+            if word == "..." || is_move_number(word) {
+                continue;
+            }
+            let at = parse_position(word, &board)?;
+            let player = board.whose_turn().ok_or(RecordError::IllegalMove {
+                ply: moves.len() + 1,
+                error: MoveError::GameOver,
+            })?;
+            board
+                .try_add_move(player, at)
+                .map_err(|error| RecordError::IllegalMove {
+                    ply: moves.len() + 1,
+                    error,
+                })?;
+            moves.push(at);
+        }
+
+        let actual = result_tag(&board.get_game_result());
+        if let Some((_name, expected)) = tags.iter().find(|(name, _value)| name == "Result") {
+            if expected != actual {
+                return Err(RecordError::ResultMismatch {
+                    expected: expected.clone(),
+                    actual: actual.to_string(),
+                });
+            }
+        }
+
+        Ok(GameRecord { tags, start, moves })
+    }
+}
+
+/// The reasons a game record can fail to parse.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RecordError {
+    /// A header line is not of the form [Name "Value"].
+    InvalidTag(String),
+    /// The Variant tag is not of the form ROWSxCOLUMNS/RUN, or does not match the Position tag.
+    InvalidVariant(String),
+    /// The Rules tag is neither "standard" nor "misere".
+    InvalidRules(String),
+    /// The Position tag does not hold a valid board.
+    InvalidPosition(ParseBoardError),
+    /// A move is not a valid position on the board.
+    InvalidNotation(String),
+    /// The move (counting from 1) is not allowed by the rules.
+    IllegalMove { ply: usize, error: MoveError },
+    /// The Result tag does not match the result of the moves.
+    ResultMismatch { expected: String, actual: String },
+}
+
+impl std::fmt::Display for RecordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordError::InvalidTag(line) => write!(f, "Invalid tag: {}", line),
+            RecordError::InvalidVariant(variant) => write!(f, "Invalid variant: '{}'", variant),
+            RecordError::InvalidRules(rules) => write!(f, "Invalid rules: '{}'", rules),
+            RecordError::InvalidPosition(error) => write!(f, "Invalid position: {}", error),
+            RecordError::InvalidNotation(word) => write!(f, "Invalid move: '{}'", word),
+            RecordError::IllegalMove { ply, error } => {
+                write!(f, "Illegal move {}: {}", ply, error)
+            }
+            RecordError::ResultMismatch { expected, actual } => write!(
+                f,
+                "The result should be \"{}\", but the moves give \"{}\"",
+                expected, actual
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RecordError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RecordError::InvalidPosition(error) => Some(error),
+            RecordError::IllegalMove { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the position in coordinate notation, e.g. "a1" for the top left.
+pub fn format_position(position: Position) -> String {
+    format!(
+        "{}{}",
+        (b'a' + position.column()) as char,
+        position.row() + 1
+    )
+}
+
+/// Parses a position in coordinate notation ("b2"),
+/// or as a number counting row by row from the top left ("5"), for the given board.
+///
+/// Examples
+/// ```
+/// use tictactoelib::board::{Board, Position};
+/// use tictactoelib::record::parse_position;
+/// let board = Board::new();
+/// assert_eq!(parse_position("c1", &board), Ok(Position::new(0, 2)));
+/// assert_eq!(parse_position("3", &board), Ok(Position::new(0, 2)));
+/// assert!(parse_position("d1", &board).is_err());
+/// ```
+pub fn parse_position(word: &str, board: &Board) -> Result<Position, RecordError> {
+    let invalid = || RecordError::InvalidNotation(word.to_string());
+    let mut chars = word.chars();
+    let first = chars.next().ok_or_else(invalid)?;
+    let (row, column) = if first.is_ascii_lowercase() {
+        let row: u8 = chars.as_str().parse().map_err(|_| invalid())?;
+        (row.checked_sub(1).ok_or_else(invalid)?, first as u8 - b'a')
+    } else {
+        let number: usize = word.parse().map_err(|_| invalid())?;
+        let index = number.checked_sub(1).ok_or_else(invalid)?;
+        let columns = board.columns() as usize;
+        if index >= board.rows() as usize * columns {
+            return Err(invalid());
+        }
+        ((index / columns) as u8, (index % columns) as u8)
+    };
+    board.try_position(row, column).map_err(|_| invalid())
+}
+
+/// Returns the Result tag value for a game result.
+fn result_tag(result: &GameResult) -> &'static str {
+    match result {
+        GameResult::Win(Token::X) => "X",
+        GameResult::Win(Token::O) => "O",
+        GameResult::Draw => "Draw",
+        GameResult::InProgress => "*",
+    }
+}
+
+/// Parses a header line of the form [Name "Value"].
+fn parse_tag(line: &str) -> Result<(String, String), RecordError> {
+    let invalid = || RecordError::InvalidTag(line.to_string());
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|line| line.strip_suffix(']'))
+        .ok_or_else(invalid)?;
+    let (name, quoted) = inner.split_at(inner.find(' ').ok_or_else(invalid)?);
+    let quoted = quoted.trim();
+    if name.is_empty() || quoted.len() < 2 || !quoted.starts_with('"') || !quoted.ends_with('"') {
+        return Err(invalid());
+    }
+
+    let mut value = String::new();
+    let mut chars = quoted[1..quoted.len() - 1].chars();
+    while let Some(the_char) = chars.next() {
+        match the_char {
+            '\\' => value.push(chars.next().ok_or_else(invalid)?),
+            '"' => return Err(invalid()),
+            _ => value.push(the_char),
+        }
+    }
+    Ok((name.to_string(), value))
+}
+
+/// Parses a Variant tag value ("ROWSxCOLUMNS/RUN") into an empty board.
+fn parse_variant(variant: &str) -> Result<Board, RecordError> {
+    let invalid = || RecordError::InvalidVariant(variant.to_string());
+    let (size, run_length) = variant.split_at(variant.find('/').ok_or_else(invalid)?);
+    let (rows, columns) = size.split_at(size.find('x').ok_or_else(invalid)?);
+    let rows: u8 = rows.parse().map_err(|_| invalid())?;
+    let columns: u8 = columns[1..].parse().map_err(|_| invalid())?;
+    let run_length: u8 = run_length[1..].parse().map_err(|_| invalid())?;
+    if rows == 0
+        || columns == 0
+        || rows > crate::board::MAX_SIZE
+        || columns > crate::board::MAX_SIZE
+        || run_length == 0
+        || run_length > std::cmp::max(rows, columns)
+    {
+        return Err(invalid());
+    }
+    Ok(Board::with_size(rows, columns, run_length))
+}
+
+/// Indicates whether or not the word is a move number, like "12."
+fn is_move_number(word: &str) -> bool {
+    word.len() > 1
+        && word.ends_with('.')
+        && word[..word.len() - 1]
+            .chars()
+            .all(|the_char| the_char.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::optimal::OptimalPlayer;
+
+    const DRAWN_GAME: &str = "[X \"Alice\"]
+[O \"OptimalPlayer\"]
+[Date \"2020-04-01\"]
+[Variant \"3x3/3\"]
+[Result \"Draw\"]
+
+1. b2 a1
+2. c1 a3
+3. a2 c2
+4. b1 b3
+5. c3
+";
+
+    #[test]
+    fn test_parse_record() {
+        let record: GameRecord = DRAWN_GAME.parse().unwrap();
+        assert_eq!(record.tag("X"), Some("Alice"));
+        assert_eq!(record.tag("Date"), Some("2020-04-01"));
+        assert_eq!(record.tag("Missing"), None);
+        assert_eq!(record.moves().len(), 9);
+        assert_eq!(record.moves()[0], Position::new(1, 1));
+        assert_eq!(record.final_board().get_game_result(), GameResult::Draw);
+    }
+
+    #[test]
+    fn test_write_record_round_trip() {
+        let record: GameRecord = DRAWN_GAME.parse().unwrap();
+        assert_eq!(record.to_string(), DRAWN_GAME);
+    }
+
+    #[test]
+    fn test_numeric_notation() {
+        let record: GameRecord = "1. 5 1 2. 3 7 3. 4 6 4. 2 8 5. 9".parse().unwrap();
+        let expected: GameRecord = DRAWN_GAME.parse().unwrap();
+        assert_eq!(record.moves(), expected.moves());
+    }
+
+    #[test]
+    fn test_record_from_game() {
+        let game = Game::new(OptimalPlayer::new(), OptimalPlayer::new());
+        let mut record = GameRecord::from_board(game.board());
+        record.set_tag("X", "OptimalPlayer");
+        record.set_tag("O", "OptimalPlayer");
+        assert_eq!(record.tag("Result"), Some("Draw"));
+
+        let reloaded: GameRecord = record.to_string().parse().unwrap();
+        assert_eq!(reloaded, record);
+        assert_eq!(reloaded.final_board(), *game.board());
+        assert_eq!(reloaded.final_board().moves(), game.board().moves());
+    }
+
+    #[test]
+    fn test_replay() {
+        let record: GameRecord = DRAWN_GAME.parse().unwrap();
+        let boards: Vec<Board> = record.replay().collect();
+        assert_eq!(boards.len(), 9);
+        assert_eq!(boards[0].to_compact_string(), "----X----");
+        assert_eq!(boards[1].to_compact_string(), "O---X----");
+        for (board, at) in boards.iter().zip(record.moves()) {
+            assert_eq!(board.last_move(), Some(*at));
+        }
+    }
+
+    #[test]
+    fn test_other_variants() {
+        let mut board = Board::with_size(15, 15, 5);
+        board.add_move(Token::X, Position::new(14, 14));
+        board.add_move(Token::O, Position::new(0, 9));
+        let record = GameRecord::from_board(&board);
+        let text = record.to_string();
+        assert!(text.contains("[Variant \"15x15/5\"]"));
+        assert!(text.ends_with("1. o15 j1\n"));
+        let reloaded: GameRecord = text.parse().unwrap();
+        assert_eq!(reloaded.final_board(), board);
+    }
+
+    #[test]
+    fn test_starting_position() {
+        let mut board = Board::from_string(
+            "XO-\
+             ---\
+             ---",
+        );
+        board.add_move(Token::X, Position::new(1, 1));
+        board.add_move(Token::O, Position::new(2, 2));
+        let record = GameRecord::from_board(&board);
+        assert_eq!(record.tag("Position"), Some("XO-------"));
+        assert_eq!(record.moves().len(), 2);
+
+        let reloaded: GameRecord = record.to_string().parse().unwrap();
+        assert_eq!(reloaded.final_board(), board);
+
+        let mut o_first = Board::from_string("X--------");
+        o_first.add_move(Token::O, Position::new(1, 1));
+        let text = GameRecord::from_board(&o_first).to_string();
+        assert!(text.ends_with("1. ... b2\n"));
+        let reloaded: GameRecord = text.parse().unwrap();
+        assert_eq!(reloaded.final_board(), o_first);
+    }
+
+    #[test]
+    fn test_misere_round_trip() {
+        // X completes the top row, and so loses.
+        let mut board = Board::misere();
+        for column in 0..2 {
+            board.add_move(Token::X, Position::new(0, column));
+            board.add_move(Token::O, Position::new(1, column));
+        }
+        board.add_move(Token::X, Position::new(0, 2));
+        assert_eq!(board.get_game_result(), GameResult::Win(Token::O));
+
+        let record = GameRecord::from_board(&board);
+        assert_eq!(record.tag("Rules"), Some("misere"));
+        assert_eq!(record.tag("Position"), None);
+        assert_eq!(record.tag("Result"), Some("O"));
+        let text = record.to_string();
+        assert!(text.starts_with("[Variant \"3x3/3\"]\n[Rules \"misere\"]\n"));
+
+        let reloaded: GameRecord = text.parse().unwrap();
+        assert_eq!(reloaded, record);
+        assert_eq!(reloaded.start().rules(), Rules::Misere);
+        assert_eq!(reloaded.final_board(), board);
+        assert_eq!(GameRecord::from_board(&Board::new()).tag("Rules"), None);
+    }
+
+    #[test]
+    fn test_tag_escaping() {
+        let mut record = GameRecord::from_board(&Board::new());
+        record.set_tag("X", "Robin \"The Rock\" \\o/");
+        let reloaded: GameRecord = record.to_string().parse().unwrap();
+        assert_eq!(reloaded.tag("X"), Some("Robin \"The Rock\" \\o/"));
+    }
+
+    #[test]
+    fn test_invalid_records() {
+        assert_eq!(
+            "1. b2 b2".parse::<GameRecord>(),
+            Err(RecordError::IllegalMove {
+                ply: 2,
+                error: MoveError::Occupied(Position::new(1, 1))
+            })
+        );
+        assert_eq!(
+            "1. a1 b1 2. a2 b2 3. a3 c3".parse::<GameRecord>(),
+            Err(RecordError::IllegalMove {
+                ply: 6,
+                error: MoveError::GameOver
+            })
+        );
+        assert_eq!(
+            "1. d4".parse::<GameRecord>(),
+            Err(RecordError::InvalidNotation("d4".to_string()))
+        );
+        assert_eq!(
+            "1. 0".parse::<GameRecord>(),
+            Err(RecordError::InvalidNotation("0".to_string()))
+        );
+        assert_eq!(
+            "[Result \"X\"]\n1. b2".parse::<GameRecord>(),
+            Err(RecordError::ResultMismatch {
+                expected: "X".to_string(),
+                actual: "*".to_string()
+            })
+        );
+        assert_eq!(
+            "[Variant \"3by3\"]".parse::<GameRecord>(),
+            Err(RecordError::InvalidVariant("3by3".to_string()))
+        );
+        assert_eq!(
+            "[Rules \"suicide\"]".parse::<GameRecord>(),
+            Err(RecordError::InvalidRules("suicide".to_string()))
+        );
+        assert_eq!(
+            "[X Alice]".parse::<GameRecord>(),
+            Err(RecordError::InvalidTag("[X Alice]".to_string()))
+        );
+        assert_eq!(
+            "[Position \"XX-------\"]".parse::<GameRecord>(),
+            Err(RecordError::InvalidPosition(
+                ParseBoardError::InvalidTokenCount {
+                    x_count: 2,
+                    o_count: 0
+                }
+            ))
+        );
+    }
+} // mod tests
@@ -0,0 +1,160 @@
+//! The text files the learning players save what they have learned in.
+//!
+//! A file starts with a header line naming what it holds and the version of its format,
+//! followed by one entry per line, in sorted order so that saving the same table twice
+//! gives the same file.
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// The header and version of one kind of saved file.
+pub(crate) struct Format {
+    /// The start of the first line, which is followed by the version.
+    pub header: &'static str,
+    pub version: u32,
+    /// What the file holds, for error messages.
+    pub name: &'static str,
+}
+
+impl Format {
+    /// Writes the header line, then the lines in sorted order.
+    pub fn write(&self, writer: &mut impl Write, mut lines: Vec<String>) -> std::io::Result<()> {
+        writeln!(writer, "{} {}", self.header, self.version)?;
+        lines.sort();
+        for line in lines {
+            writeln!(writer, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    /// Checks the header line, then parses every other line,
+    /// with a function that returns None if the line is not valid.
+    pub fn read<T>(
+        &self,
+        reader: &mut impl BufRead,
+        mut parse_line: impl FnMut(&str) -> Option<T>,
+    ) -> Result<Vec<T>, LoadError> {
+        let mut lines = reader.lines();
+        let header = lines.next().ok_or(LoadError::WrongHeader(self.name))??;
+        let version = header
+            .strip_prefix(self.header)
+            .and_then(|version| version.trim().parse::<u32>().ok())
+            .ok_or(LoadError::WrongHeader(self.name))?;
+        if version != self.version {
+            return Err(LoadError::UnsupportedVersion(self.name, version));
+        }
+
+        let mut entries = Vec::new();
+        for (index, line) in lines.enumerate() {
+            match parse_line(&line?) {
+                Some(entry) => entries.push(entry),
+                None => return Err(LoadError::InvalidLine(self.name, index + 2)),
+            }
+        }
+        Ok(entries)
+    }
+}
+
+/// Creates the file, and writes to it with the function.
+pub(crate) fn save(
+    path: impl AsRef<Path>,
+    write: impl FnOnce(&mut BufWriter<File>) -> std::io::Result<()>,
+) -> std::io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    write(&mut file)?;
+    file.flush()
+}
+
+/// Opens the file, and reads from it with the function.
+pub(crate) fn load<T>(
+    path: impl AsRef<Path>,
+    read: impl FnOnce(&mut BufReader<File>) -> Result<T, LoadError>,
+) -> Result<T, LoadError> {
+    read(&mut BufReader::new(File::open(path)?))
+}
+
+/// A problem reading a saved file.  Each problem but Io names what the file should hold.
+#[derive(Debug)]
+pub enum LoadError {
+    Io(std::io::Error),
+    /// The file does not start with the header for what it should hold.
+    WrongHeader(&'static str),
+    UnsupportedVersion(&'static str, u32),
+    /// A line, numbered from one, that is not a valid entry.
+    InvalidLine(&'static str, usize),
+}
+
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LoadError::Io(error) => write!(f, "Cannot read saved file: {}", error),
+            LoadError::WrongHeader(name) => write!(f, "Not a {} file", name),
+            LoadError::UnsupportedVersion(name, version) => {
+                write!(f, "Unsupported {} version {}", name, version)
+            }
+            LoadError::InvalidLine(name, number) => write!(f, "Invalid {} line {}", name, number),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for LoadError {
+    fn from(error: std::io::Error) -> LoadError {
+        LoadError::Io(error)
+    }
+}
+
+#[cfg(test)]
+mod saved_tests {
+    use super::*;
+
+    const FORMAT: Format = Format {
+        header: "tictactoe numbers",
+        version: 3,
+        name: "number list",
+    };
+
+    #[test]
+    fn test_write_and_read() {
+        let mut text = Vec::new();
+        FORMAT
+            .write(&mut text, vec!["2".to_string(), "1".to_string()])
+            .unwrap();
+        assert_eq!(text, b"tictactoe numbers 3\n1\n2\n");
+        let numbers = FORMAT.read(&mut text.as_slice(), |line| line.parse::<u8>().ok());
+        assert_eq!(numbers.unwrap(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_read_errors() {
+        let read = |text: &str| {
+            FORMAT
+                .read(&mut text.as_bytes(), |line| line.parse::<u8>().ok())
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(read(""), "Not a number list file");
+        assert_eq!(read("tictactoe numbers three"), "Not a number list file");
+        assert_eq!(
+            read("tictactoe numbers 4\n1\n"),
+            "Unsupported number list version 4"
+        );
+        assert_eq!(
+            read("tictactoe numbers 3\n1\nx\n"),
+            "Invalid number list line 3"
+        );
+        let missing = load(
+            std::env::temp_dir().join("tictactoe_no_such_file"),
+            |file| FORMAT.read(file, |line| line.parse::<u8>().ok()),
+        );
+        assert!(matches!(missing, Err(LoadError::Io(_))));
+    }
+} // mod saved_tests
@@ -0,0 +1,348 @@
+//! Batch simulation of many games between two strategies, spread over several threads.
+//!
+//! The games are split into fixed-size chunks, and each chunk gets new players, seeded from
+//! the simulation's seed and the chunk's number.  The totals therefore depend only on the
+//! seed, and not on the number of threads or the order in which the chunks are played.
+use crate::board::{Board, GameResult, Position, Token};
+use crate::game::Game;
+use crate::player::Player;
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// The number of games played by one pair of players, before they are replaced.
+const CHUNK_SIZE: u64 = 1000;
+
+/// Counts of how games ended.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Outcomes {
+    pub x_wins: u64,
+    pub o_wins: u64,
+    pub draws: u64,
+}
+
+impl Outcomes {
+    /// Returns the number of games counted.
+    pub fn games(self) -> u64 {
+        self.x_wins + self.o_wins + self.draws
+    }
+
+    fn add(&mut self, result: &GameResult) {
+        match result {
+            GameResult::Win(Token::X) => self.x_wins += 1,
+            GameResult::Win(Token::O) => self.o_wins += 1,
+            GameResult::Draw => self.draws += 1,
+            GameResult::InProgress => panic!("Game should be over!"),
+        }
+    }
+
+    fn merge(&mut self, other: Outcomes) {
+        self.x_wins += other.x_wins;
+        self.o_wins += other.o_wins;
+        self.draws += other.draws;
+    }
+}
+
+/// The aggregated results of a simulation.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Report {
+    outcomes: Outcomes,
+    total_plies: u64,
+    by_first_move: HashMap<Position, Outcomes>,
+}
+
+impl Report {
+    /// Returns how all of the games ended.
+    pub fn outcomes(&self) -> Outcomes {
+        self.outcomes
+    }
+
+    /// Returns the number of games played.
+    pub fn games(&self) -> u64 {
+        self.outcomes.games()
+    }
+
+    /// Returns the average number of moves played in each game.
+    pub fn average_length(&self) -> f64 {
+        if self.games() == 0 {
+            return 0.0;
+        }
+        self.total_plies as f64 / self.games() as f64
+    }
+
+    /// Returns how the games ended, for each first move that was played, in board order.
+    pub fn by_first_move(&self) -> Vec<(Position, Outcomes)> {
+        let mut by_first_move: Vec<(Position, Outcomes)> = self
+            .by_first_move
+            .iter()
+            .map(|(position, outcomes)| (*position, *outcomes))
+            .collect();
+        by_first_move.sort_by_key(|(position, _outcomes)| (position.row(), position.column()));
+        by_first_move
+    }
+
+    fn merge(&mut self, other: Report) {
+        self.outcomes.merge(other.outcomes);
+        self.total_plies += other.total_plies;
+        for (position, outcomes) in other.by_first_move {
+            self.by_first_move
+                .entry(position)
+                .or_default()
+                .merge(outcomes);
+        }
+    }
+}
+
+impl std::fmt::Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let percent = |count: u64| 100.0 * count as f64 / self.games().max(1) as f64;
+        let outcomes = self.outcomes;
+        writeln!(f, "Games:   {}", self.games())?;
+        writeln!(
+            f,
+            "X wins:  {} ({:.1}%)",
+            outcomes.x_wins,
+            percent(outcomes.x_wins)
+        )?;
+        writeln!(
+            f,
+            "O wins:  {} ({:.1}%)",
+            outcomes.o_wins,
+            percent(outcomes.o_wins)
+        )?;
+        writeln!(
+            f,
+            "Draws:   {} ({:.1}%)",
+            outcomes.draws,
+            percent(outcomes.draws)
+        )?;
+        writeln!(f, "Average length: {:.2} moves", self.average_length())?;
+        writeln!(f, "First move      Games     X wins     O wins      Draws")?;
+        for (position, outcomes) in self.by_first_move() {
+            writeln!(
+                f,
+                "{:>10} {:>10} {:>10} {:>10} {:>10}",
+                crate::record::format_position(position),
+                outcomes.games(),
+                outcomes.x_wins,
+                outcomes.o_wins,
+                outcomes.draws
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Plays many games between two strategies, on several threads.
+///
+/// # Examples
+/// ```
+/// use tictactoelib::simulation::Simulation;
+/// use tictactoelib::strategies::RandomPlayer;
+///
+/// let simulation = Simulation::new(2000, 42);
+/// let report = simulation.run(RandomPlayer::with_seed, RandomPlayer::with_seed);
+/// assert_eq!(report.games(), 2000);
+/// assert_eq!(report, simulation.run(RandomPlayer::with_seed, RandomPlayer::with_seed));
+/// ```
+pub struct Simulation {
+    games: u64,
+    seed: u64,
+    threads: usize,
+    start: Board,
+}
+
+impl Simulation {
+    /// Creates a simulation of the given number of games, from an empty board,
+    /// using as many threads as the machine has processors.
+    pub fn new(games: u64, seed: u64) -> Simulation {
+        let threads = std::thread::available_parallelism()
+            .map(|threads| threads.get())
+            .unwrap_or(1);
+        Simulation {
+            games,
+            seed,
+            threads,
+            start: Board::new(),
+        }
+    }
+
+    /// Changes the number of threads to play the games on.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of threads is zero.
+    pub fn set_threads(&mut self, threads: usize) {
+        if threads == 0 {
+            panic!("A simulation needs at least one thread!");
+        }
+        self.threads = threads;
+    }
+
+    /// Changes the board that every game starts from.
+    pub fn set_start(&mut self, start: Board) {
+        self.start = start;
+    }
+
+    /// Plays all of the games, and returns the aggregated results.
+    ///
+    /// Each factory is given a seed, and returns a player to use for a chunk of games.
+    /// The players are created on the thread that uses them, so they need not be Send.
+    pub fn run<X, O, FX, FO>(&self, x_factory: FX, o_factory: FO) -> Report
+    where
+        X: Player,
+        O: Player,
+        FX: Fn(u64) -> X + Sync,
+        FO: Fn(u64) -> O + Sync,
+    {
+        let chunks = chunk_count(self.games);
+        let next_chunk = AtomicU64::new(0);
+        let total = Mutex::new(Report::default());
+
+        std::thread::scope(|scope| {
+            for _thread in 0..self.threads {
+                scope.spawn(|| {
+                    let mut report = Report::default();
+                    loop {
+                        let chunk = next_chunk.fetch_add(1, Ordering::Relaxed);
+                        if chunk >= chunks {
+                            break;
+                        }
+                        let mut x = x_factory(mix(self.seed, 2 * chunk));
+                        let mut o = o_factory(mix(self.seed, 2 * chunk + 1));
+                        for _game in 0..chunk_len(self.games, chunk) {
+                            self.play(&mut x, &mut o, &mut report);
+                        }
+                    }
+                    total.lock().unwrap().merge(report);
+                });
+            }
+        });
+
+        total.into_inner().unwrap()
+    }
+
+    fn play(&self, x: impl Player, o: impl Player, report: &mut Report) {
+        let mut game = Game::start_from(self.start, x, o);
+        let first_move = game.step();
+        let mut plies = 0;
+        if first_move.is_some() {
+            plies += 1;
+            while game.step().is_some() {
+                plies += 1;
+            }
+        }
+
+        let result = game.result();
+        report.outcomes.add(&result);
+        report.total_plies += plies;
+        if let Some(position) = first_move {
+            report
+                .by_first_move
+                .entry(position)
+                .or_default()
+                .add(&result);
+        }
+    }
+}
+
+/// Returns the number of chunks needed for the games, without overflowing.
+fn chunk_count(games: u64) -> u64 {
+    games / CHUNK_SIZE + u64::from(games % CHUNK_SIZE != 0)
+}
+
+/// Returns the number of games in a chunk; only the last one can be short.
+fn chunk_len(games: u64, chunk: u64) -> u64 {
+    (games - chunk * CHUNK_SIZE).min(CHUNK_SIZE)
+}
+
+/// Combines the simulation's seed with a stream number, into a well-mixed seed (SplitMix64).
+fn mix(seed: u64, stream: u64) -> u64 {
+    let mut z = seed.wrapping_add(stream.wrapping_add(1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod simulation_tests {
+    use super::*;
+    use crate::optimal::OptimalPlayer;
+    use crate::strategies::RandomPlayer;
+
+    fn random_simulation(games: u64, seed: u64, threads: usize) -> Report {
+        let mut simulation = Simulation::new(games, seed);
+        simulation.set_threads(threads);
+        simulation.run(RandomPlayer::with_seed, RandomPlayer::with_seed)
+    }
+
+    #[test]
+    fn test_counts_add_up() {
+        let report = random_simulation(10_500, 1, 4);
+        assert_eq!(report.games(), 10_500);
+        let first_moves = report.by_first_move();
+        assert_eq!(first_moves.len(), 9);
+        let mut total = Outcomes::default();
+        for (_position, outcomes) in first_moves {
+            total.merge(outcomes);
+        }
+        assert_eq!(total, report.outcomes());
+
+        // Random X wins far more often than random O, and the games last 5 to 9 moves.
+        let outcomes = report.outcomes();
+        assert_gt!(outcomes.x_wins, 2 * outcomes.o_wins);
+        assert_gt!(outcomes.draws, 0);
+        assert!(report.average_length() > 5.0 && report.average_length() < 9.0);
+    }
+
+    #[test]
+    fn test_independent_of_threads() {
+        let report = random_simulation(5000, 7, 1);
+        assert_eq!(report, random_simulation(5000, 7, 3));
+        assert_eq!(report, random_simulation(5000, 7, 8));
+        assert_ne!(report, random_simulation(5000, 8, 3));
+    }
+
+    #[test]
+    fn test_optimal_against_random() {
+        let mut simulation = Simulation::new(2000, 3);
+        simulation.set_threads(2);
+        let report = simulation.run(|_seed| OptimalPlayer::new(), RandomPlayer::with_seed);
+        assert_eq!(report.outcomes().o_wins, 0);
+        // The optimal player always opens in the same place.
+        assert_eq!(report.by_first_move().len(), 1);
+    }
+
+    #[test]
+    fn test_from_finished_board() {
+        let mut simulation = Simulation::new(10, 0);
+        simulation.set_start(Board::from_string(
+            "XXX\
+             OO-\
+             ---",
+        ));
+        let report = simulation.run(RandomPlayer::with_seed, RandomPlayer::with_seed);
+        assert_eq!(report.outcomes().x_wins, 10);
+        assert_eq!(report.average_length(), 0.0);
+        assert!(report.by_first_move().is_empty());
+    }
+
+    #[test]
+    fn test_chunks() {
+        assert_eq!(chunk_count(0), 0);
+        assert_eq!(chunk_count(1000), 1);
+        assert_eq!(chunk_count(1001), 2);
+        assert_eq!(chunk_len(1001, 1), 1);
+        let last = chunk_count(u64::MAX) - 1;
+        assert_eq!(last, u64::MAX / 1000);
+        assert_eq!(chunk_len(u64::MAX, last), u64::MAX % 1000);
+        assert_eq!(chunk_len(u64::MAX, 0), 1000);
+    }
+
+    #[test]
+    #[should_panic(expected = "A simulation needs at least one thread!")]
+    fn test_no_threads() {
+        Simulation::new(1, 0).set_threads(0);
+    }
+} // mod simulation_tests
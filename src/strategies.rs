@@ -1,60 +1,288 @@
+//! Simple, non-optimal strategies for playing the game.
 use crate::board::{Board, Position};
 use crate::player::Player;
+use crate::ultimate::{UltimateBoard, UltimateMove, UltimatePlayer};
 
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
 
-pub struct RandomPlayer {}
+/// A player that chooses uniformly at random from the empty positions.
+///
+/// It draws its moves from any random number generator,
+/// so a seeded generator gives the same sequence of games every time.
+pub struct RandomPlayer<R: RngCore = StdRng> {
+    rng: R,
+}
 
-impl Player for RandomPlayer {
-    fn take_turn(&mut self, board: &Board) -> Position {
-        let mut rng = rand::thread_rng();
+impl RandomPlayer {
+    /// Creates a player seeded from the operating system's entropy source.
+    pub fn new() -> RandomPlayer {
+        RandomPlayer::with_rng(StdRng::from_entropy())
+    }
+
+    /// Creates a player whose moves are reproducible for a given seed.
+    ///
+    /// # Examples
+    /// ```
+    /// use tictactoelib::board::Board;
+    /// use tictactoelib::player::Player;
+    /// use tictactoelib::strategies::RandomPlayer;
+    ///
+    /// let board = Board::new();
+    /// let first = RandomPlayer::with_seed(7).take_turn(&board);
+    /// let second = RandomPlayer::with_seed(7).take_turn(&board);
+    /// assert_eq!(first, second);
+    /// ```
+    pub fn with_seed(seed: u64) -> RandomPlayer {
+        RandomPlayer::with_rng(StdRng::seed_from_u64(seed))
+    }
+}
+
+impl<R: RngCore> RandomPlayer<R> {
+    /// Creates a player that draws its moves from the given random number generator.
+    pub fn with_rng(rng: R) -> RandomPlayer<R> {
+        RandomPlayer { rng }
+    }
+}
+
+impl Default for RandomPlayer {
+    fn default() -> RandomPlayer {
+        RandomPlayer::new()
+    }
+}
 
+impl<R: RngCore> Player for RandomPlayer<R> {
+    fn take_turn(&mut self, board: &Board) -> Position {
         let empty_positions = board.empty_positions();
         let count = empty_positions.len();
-        let position_to_choose = rng.gen_range(0, count);
+        let position_to_choose = self.rng.gen_range(0, count);
         empty_positions[position_to_choose]
     }
 }
 
+impl<R: RngCore> UltimatePlayer for RandomPlayer<R> {
+    fn take_turn(&mut self, board: &UltimateBoard) -> UltimateMove {
+        let legal_moves = board.legal_moves();
+        let move_to_choose = self.rng.gen_range(0, legal_moves.len());
+        legal_moves[move_to_choose]
+    }
+}
+
+/// The squares a WeightedRandomPlayer favours.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Preference {
+    /// The middle square, or the middle squares of a board with an even size.
+    Center,
+    /// The four corner squares.
+    Corners,
+}
+
+impl Preference {
+    /// How many times more likely a preferred square is to be chosen than any other.
+    pub const WEIGHT: u32 = 4;
+
+    /// Returns whether the position is one of the preferred squares on the board.
+    ///
+    /// # Examples
+    /// ```
+    /// use tictactoelib::board::{Board, Position};
+    /// use tictactoelib::strategies::Preference;
+    ///
+    /// let board = Board::new();
+    /// assert!(Preference::Center.prefers(Position::new(1, 1), &board));
+    /// assert!(Preference::Corners.prefers(Position::new(2, 0), &board));
+    /// assert!(!Preference::Corners.prefers(Position::new(1, 0), &board));
+    /// ```
+    pub fn prefers(self, position: Position, board: &Board) -> bool {
+        let is_middle = |value: u8, size: u8| value == (size - 1) / 2 || value == size / 2;
+        let is_end = |value: u8, size: u8| value == 0 || value == size - 1;
+        let (row, column) = (position.row(), position.column());
+        match self {
+            Preference::Center => {
+                is_middle(row, board.rows()) && is_middle(column, board.columns())
+            }
+            Preference::Corners => is_end(row, board.rows()) && is_end(column, board.columns()),
+        }
+    }
+
+    fn weight(self, position: Position, board: &Board) -> u32 {
+        if self.prefers(position, board) {
+            Preference::WEIGHT
+        } else {
+            1
+        }
+    }
+}
+
+/// A player that chooses at random, but favours some squares over others.
+pub struct WeightedRandomPlayer<R: RngCore = StdRng> {
+    preference: Preference,
+    rng: R,
+}
+
+impl WeightedRandomPlayer {
+    /// Creates a player seeded from the operating system's entropy source.
+    pub fn new(preference: Preference) -> WeightedRandomPlayer {
+        WeightedRandomPlayer::with_rng(preference, StdRng::from_entropy())
+    }
+
+    /// Creates a player whose moves are reproducible for a given seed.
+    pub fn with_seed(preference: Preference, seed: u64) -> WeightedRandomPlayer {
+        WeightedRandomPlayer::with_rng(preference, StdRng::seed_from_u64(seed))
+    }
+}
+
+impl<R: RngCore> WeightedRandomPlayer<R> {
+    /// Creates a player that draws its moves from the given random number generator.
+    pub fn with_rng(preference: Preference, rng: R) -> WeightedRandomPlayer<R> {
+        WeightedRandomPlayer { preference, rng }
+    }
+
+    /// Returns the squares this player favours.
+    pub fn preference(&self) -> Preference {
+        self.preference
+    }
+}
+
+impl<R: RngCore> Player for WeightedRandomPlayer<R> {
+    fn take_turn(&mut self, board: &Board) -> Position {
+        let empty_positions = board.empty_positions();
+        let total: u32 = empty_positions
+            .iter()
+            .map(|position| self.preference.weight(*position, board))
+            .sum();
+        let mut choice = self.rng.gen_range(0, total);
+        for position in empty_positions {
+            let weight = self.preference.weight(position, board);
+            if choice < weight {
+                return position;
+            }
+            choice -= weight;
+        }
+        unreachable!("The choice is always less than the total weight");
+    }
+}
+
+#[cfg(test)]
 mod random_player_tests {
     use super::*;
     use crate::board::GameResult;
+    use rand::rngs::mock::StepRng;
+    use std::collections::HashMap;
 
-    #[test]
-    fn test_random_played_boards_differ() {
-        let mut player = RandomPlayer {};
-        //for _n in 1..1000 { // Uncomment to tune MAX_SAME_GAMES
-        const MAX_GAMES: u32 = 100;
-        const MAX_SAME_GAMES: u32 = 35; // Determined by running this many many times!
-        println!("Play {} games with players that move randomly.", MAX_GAMES);
-        println!("The games should be different!");
+    fn play_games(player: &mut impl Player, count: u32) -> Vec<Board> {
         let mut games = Vec::<Board>::new();
-        for game in 1..MAX_GAMES + 1 {
+        for _game in 0..count {
             let mut board = Board::new();
-            for _turn in 1..10 {
+            while board.get_game_result() == GameResult::InProgress {
                 let whose_turn = board.whose_turn().unwrap();
                 let position = player.take_turn(&board);
                 board.add_move(whose_turn, position);
-                if let GameResult::Win(_winner) = board.get_game_result() {
-                    break;
-                }
             }
-            println!("Game #{}:\n{}", game, board);
             games.push(board);
         }
+        games
+    }
+
+    fn first_moves(player: &mut impl Player, count: u32) -> Vec<Position> {
+        (0..count)
+            .map(|_| player.take_turn(&Board::new()))
+            .collect()
+    }
+
+    #[test]
+    fn test_random_played_boards_differ() {
+        const MAX_GAMES: u32 = 100;
+        const MAX_SAME_GAMES: u32 = 35;
+        println!("Play {} games with players that move randomly.", MAX_GAMES);
+        println!("The games should be different!");
+        let games = play_games(&mut RandomPlayer::with_seed(2020), MAX_GAMES);
 
+        let mut counts = HashMap::<Board, u32>::new();
+        for game in games.iter() {
+            *counts.entry(*game).or_insert(0) += 1;
+        }
         let mut same_game_count = 0;
-        for game1 in 0..MAX_GAMES {
-            for game2 in game1 + 1..MAX_GAMES {
-                if games[game1 as usize] == games[game2 as usize] {
-                    println!("Games {} and {} are the same!", game1 + 1, game2 + 1);
-                    same_game_count += 1;
-                }
+        for (game, count) in counts.iter() {
+            if *count > 1 {
+                println!("{} games are the same:\n{}", count, game);
+                same_game_count += count * (count - 1) / 2; // pairs of games
             }
         }
 
-        // Testing indicates that although some games will be the same, not too many will be.
+        // Although some games will be the same, not too many will be.
         assert_lt!(same_game_count, MAX_SAME_GAMES + 1);
-        //} // Uncomment to tune MAX_SAME_GAMES
+    }
+
+    #[test]
+    fn test_same_seed_same_games() {
+        let first = play_games(&mut RandomPlayer::with_seed(42), 20);
+        let second = play_games(&mut RandomPlayer::with_seed(42), 20);
+        let moves = |games: &[Board]| games.iter().map(|game| game.moves()).collect::<Vec<_>>();
+        assert_eq!(moves(&first), moves(&second));
+
+        let other = play_games(&mut RandomPlayer::with_seed(43), 20);
+        assert_ne!(moves(&first), moves(&other));
+    }
+
+    #[test]
+    fn test_any_rng() {
+        // A generator that always returns zero always picks the first empty position.
+        let mut player = RandomPlayer::with_rng(StepRng::new(0, 0));
+        let games = play_games(&mut player, 1);
+        assert_eq!(
+            games[0].to_compact_string(),
+            "XOX\
+             OXO\
+             X--"
+        );
+    }
+
+    #[test]
+    fn test_center_preference() {
+        let mut player = WeightedRandomPlayer::with_seed(Preference::Center, 1);
+        let moves = first_moves(&mut player, 1200);
+        let centers = moves
+            .iter()
+            .filter(|position| **position == Position::new(1, 1));
+        // The center is chosen with a chance of 4 in 12, rather than 1 in 9.
+        assert_gt!(centers.count(), 300);
+    }
+
+    #[test]
+    fn test_corners_preference() {
+        let board = Board::new();
+        let mut player = WeightedRandomPlayer::with_seed(Preference::Corners, 1);
+        let moves = first_moves(&mut player, 1000);
+        let corners = moves
+            .iter()
+            .filter(|position| Preference::Corners.prefers(**position, &board));
+        // The corners are chosen with a chance of 16 in 21, rather than 4 in 9.
+        assert_gt!(corners.count(), 650);
+    }
+
+    #[test]
+    fn test_preferred_squares_on_larger_boards() {
+        let board = Board::with_size(4, 5, 3);
+        let preferred = |preference: Preference| {
+            board
+                .empty_positions()
+                .into_iter()
+                .filter(|position| preference.prefers(*position, &board))
+                .collect::<Vec<Position>>()
+        };
+        assert_eq!(
+            preferred(Preference::Center),
+            vec![Position::new(1, 2), Position::new(2, 2)]
+        );
+        assert_eq!(
+            preferred(Preference::Corners),
+            vec![
+                Position::new(0, 0),
+                Position::new(0, 4),
+                Position::new(3, 0),
+                Position::new(3, 4)
+            ]
+        );
     }
 } // random_player_tests
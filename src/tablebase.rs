@@ -0,0 +1,517 @@
+//! A complete table of solved positions, so that moves can be looked up rather than searched.
+//!
+//! Every position reachable from an empty board is solved once, and its value and
+//! distance to the end of the game are stored in one byte.  Positions are indexed by
+//! reading the board as a number in base 3, so a lookup is a single array access.
+use crate::board::{Board, GameResult, Position, Rules, Token};
+use crate::optimal::Evaluation;
+use crate::player::Player;
+
+use std::collections::HashSet;
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// The largest number of squares a tablebase can be built for.
+///
+/// The table has one byte for each of the 3^squares ways of filling a board.
+pub const MAX_SQUARES: usize = 12;
+
+/// The first bytes of a saved tablebase.
+const MAGIC: &[u8; 4] = b"TTTB";
+/// The version of the saved format.
+const VERSION: u8 = 1;
+
+// Each entry holds the outcome in its top two bits, and the distance to the end below them.
+const UNREACHABLE: u8 = 0;
+const WIN: u8 = 1 << 6;
+const DRAW: u8 = 2 << 6;
+const LOSS: u8 = 3 << 6;
+const OUTCOME_MASK: u8 = 3 << 6;
+const PLIES_MASK: u8 = !OUTCOME_MASK;
+
+/// The value of every reachable position on one size of board, under standard rules.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tablebase {
+    rows: u8,
+    columns: u8,
+    run_length: u8,
+    entries: Vec<u8>,
+}
+
+/// Counts of the positions in a tablebase.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Statistics {
+    /// All reachable positions, including the empty board.
+    pub positions: usize,
+    /// Reachable positions that are different up to rotation and reflection.
+    pub canonical_positions: usize,
+    /// Finished games that X has won.
+    pub x_wins: usize,
+    /// Finished games that O has won.
+    pub o_wins: usize,
+    /// Finished games that are drawn.
+    pub draws: usize,
+}
+
+impl Tablebase {
+    /// Solves every position of the standard 3x3 game.
+    ///
+    /// # Examples
+    /// ```
+    /// use tictactoelib::board::Board;
+    /// use tictactoelib::optimal::Evaluation;
+    /// use tictactoelib::tablebase::Tablebase;
+    ///
+    /// let tablebase = Tablebase::solve();
+    /// assert_eq!(tablebase.len(), 5478);
+    /// assert_eq!(tablebase.get(&Board::new()), Some(Evaluation::Draw(9)));
+    /// ```
+    pub fn solve() -> Tablebase {
+        Tablebase::solve_with_size(3, 3, 3)
+    }
+
+    /// Solves every position of a game on a board of the given size.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the board has more than MAX_SQUARES squares, or is not a valid board size.
+    pub fn solve_with_size(rows: u8, columns: u8, run_length: u8) -> Tablebase {
+        let squares = rows as usize * columns as usize;
+        if squares > MAX_SQUARES {
+            panic!("Too many squares for a tablebase: {}x{}", rows, columns);
+        }
+        let mut board = Board::with_size(rows, columns, run_length);
+        let mut tablebase = Tablebase {
+            rows,
+            columns,
+            run_length,
+            entries: vec![UNREACHABLE; 3usize.pow(squares as u32)],
+        };
+        tablebase.solve_position(&mut board);
+        tablebase
+    }
+
+    fn solve_position(&mut self, board: &mut Board) -> Evaluation {
+        let index = self.index_of(board);
+        if let Some(evaluation) = decode(self.entries[index]) {
+            return evaluation;
+        }
+
+        let evaluation = match board.get_game_result() {
+            GameResult::Win(_winner) => Evaluation::Loss(0),
+            GameResult::Draw => Evaluation::Draw(0),
+            GameResult::InProgress => {
+                let player = board.whose_turn().unwrap();
+                let mut best: Option<Evaluation> = None;
+                for position in board.empty_positions() {
+                    board.add_move(player, position);
+                    let evaluation = self.solve_position(board).for_previous_player();
+                    board.undo_move();
+                    if best.map_or(true, |best| evaluation.score() > best.score()) {
+                        best = Some(evaluation);
+                    }
+                }
+                best.unwrap()
+            }
+        };
+        self.entries[index] = encode(evaluation);
+        evaluation
+    }
+
+    /// Returns the number of reachable positions.
+    pub fn len(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| **entry != UNREACHABLE)
+            .count()
+    }
+
+    /// Returns true if no positions have been solved, which is never the case for a solved table.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the value of the position for the player whose turn it is,
+    /// or None if the board is a different size, is played by misère rules,
+    /// or the position cannot be reached.
+    ///
+    /// A finished game is a Loss(0) for the player who would be next, or a Draw(0).
+    pub fn get(&self, board: &Board) -> Option<Evaluation> {
+        if !self.fits(board) {
+            return None;
+        }
+        decode(self.entries[self.index_of(board)])
+    }
+
+    /// Returns every legal move on the board, with its value for the player whose turn it is.
+    pub fn evaluate_moves(&self, board: &Board) -> Vec<(Position, Evaluation)> {
+        let player = match board.whose_turn() {
+            Some(player) => player,
+            None => return Vec::new(),
+        };
+        board
+            .empty_positions_iter()
+            .filter_map(|position| {
+                let mut next = *board;
+                next.add_move(player, position);
+                self.get(&next)
+                    .map(|evaluation| (position, evaluation.for_previous_player()))
+            })
+            .collect()
+    }
+
+    /// Returns counts of the positions in the table.
+    pub fn statistics(&self) -> Statistics {
+        let mut statistics = Statistics::default();
+        let mut canonical = HashSet::new();
+        for (index, entry) in self.entries.iter().enumerate() {
+            let evaluation = match decode(*entry) {
+                Some(evaluation) => evaluation,
+                None => continue,
+            };
+            statistics.positions += 1;
+            let board = self.board_at(index);
+            canonical.insert(board.canonical().0);
+            match evaluation {
+                Evaluation::Draw(0) => statistics.draws += 1,
+                Evaluation::Loss(0) => match board.get_game_result() {
+                    GameResult::Win(Token::X) => statistics.x_wins += 1,
+                    _ => statistics.o_wins += 1,
+                },
+                _ => {}
+            }
+        }
+        statistics.canonical_positions = canonical.len();
+        statistics
+    }
+
+    /// Writes the table in a compact binary form: a short header, then one byte per entry.
+    pub fn write_to(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION, self.rows, self.columns, self.run_length])?;
+        writer.write_all(&self.entries)
+    }
+
+    /// Reads a table written by write_to().
+    pub fn read_from(reader: &mut impl Read) -> Result<Tablebase, TablebaseError> {
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header).map_err(truncated)?;
+        if &header[..4] != MAGIC {
+            return Err(TablebaseError::NotATablebase);
+        }
+        if header[4] != VERSION {
+            return Err(TablebaseError::UnsupportedVersion(header[4]));
+        }
+        let (rows, columns, run_length) = (header[5], header[6], header[7]);
+        let squares = rows as usize * columns as usize;
+        let valid_size = rows > 0
+            && columns > 0
+            && squares <= MAX_SQUARES
+            && run_length > 0
+            && run_length <= rows.max(columns);
+        if !valid_size {
+            return Err(TablebaseError::InvalidSize {
+                rows,
+                columns,
+                run_length,
+            });
+        }
+
+        let mut entries = vec![0u8; 3usize.pow(squares as u32)];
+        reader.read_exact(&mut entries).map_err(truncated)?;
+        if reader.read(&mut [0u8])? != 0 {
+            return Err(TablebaseError::TrailingData);
+        }
+        Ok(Tablebase {
+            rows,
+            columns,
+            run_length,
+            entries,
+        })
+    }
+
+    /// Saves the table to a file.
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        self.write_to(&mut file)?;
+        file.flush()
+    }
+
+    /// Loads a table saved by save().
+    pub fn load(path: impl AsRef<Path>) -> Result<Tablebase, TablebaseError> {
+        let mut file = std::io::BufReader::new(std::fs::File::open(path)?);
+        Tablebase::read_from(&mut file)
+    }
+
+    fn fits(&self, board: &Board) -> bool {
+        board.rows() == self.rows
+            && board.columns() == self.columns
+            && board.run_length() == self.run_length
+            && board.rules() == Rules::Standard
+    }
+
+    /// Reads the board as a number in base 3, with the top left square least significant.
+    fn index_of(&self, board: &Board) -> usize {
+        let mut index = 0;
+        for row in (0..self.rows).rev() {
+            for column in (0..self.columns).rev() {
+                let digit = match board.token_at(Position::new(row, column)) {
+                    None => 0,
+                    Some(Token::X) => 1,
+                    Some(Token::O) => 2,
+                };
+                index = index * 3 + digit;
+            }
+        }
+        index
+    }
+
+    fn board_at(&self, mut index: usize) -> Board {
+        let mut rows = Vec::new();
+        for _row in 0..self.rows {
+            let mut row = String::new();
+            for _column in 0..self.columns {
+                row.push(['-', 'X', 'O'][index % 3]);
+                index /= 3;
+            }
+            rows.push(row);
+        }
+        Board::parse_with_run_length(&rows.join("/"), self.run_length)
+            .expect("Only reachable positions are in the table")
+    }
+}
+
+fn encode(evaluation: Evaluation) -> u8 {
+    let outcome = match evaluation {
+        Evaluation::Win(_) => WIN,
+        Evaluation::Draw(_) => DRAW,
+        Evaluation::Loss(_) => LOSS,
+    };
+    outcome | evaluation.plies() as u8
+}
+
+fn decode(entry: u8) -> Option<Evaluation> {
+    let plies = (entry & PLIES_MASK) as u16;
+    match entry & OUTCOME_MASK {
+        WIN => Some(Evaluation::Win(plies)),
+        DRAW => Some(Evaluation::Draw(plies)),
+        LOSS => Some(Evaluation::Loss(plies)),
+        _ => None,
+    }
+}
+
+fn truncated(error: std::io::Error) -> TablebaseError {
+    if error.kind() == std::io::ErrorKind::UnexpectedEof {
+        TablebaseError::Truncated
+    } else {
+        TablebaseError::Io(error)
+    }
+}
+
+/// A problem reading a saved tablebase.
+#[derive(Debug)]
+pub enum TablebaseError {
+    Io(std::io::Error),
+    NotATablebase,
+    UnsupportedVersion(u8),
+    InvalidSize {
+        rows: u8,
+        columns: u8,
+        run_length: u8,
+    },
+    Truncated,
+    TrailingData,
+}
+
+impl std::fmt::Display for TablebaseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TablebaseError::Io(error) => write!(f, "Cannot read tablebase: {}", error),
+            TablebaseError::NotATablebase => write!(f, "Not a tablebase file"),
+            TablebaseError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported tablebase version {}", version)
+            }
+            TablebaseError::InvalidSize {
+                rows,
+                columns,
+                run_length,
+            } => write!(
+                f,
+                "Invalid tablebase size: {}x{}/{}",
+                rows, columns, run_length
+            ),
+            TablebaseError::Truncated => write!(f, "Tablebase file is truncated"),
+            TablebaseError::TrailingData => write!(f, "Tablebase file has extra data at the end"),
+        }
+    }
+}
+
+impl std::error::Error for TablebaseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TablebaseError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for TablebaseError {
+    fn from(error: std::io::Error) -> TablebaseError {
+        TablebaseError::Io(error)
+    }
+}
+
+/// A player that looks up every move in a tablebase, and so plays perfectly without searching.
+///
+/// Like the OptimalPlayer, it prefers the fastest win, and the slowest loss.
+pub struct TablebasePlayer {
+    tablebase: Tablebase,
+}
+
+impl TablebasePlayer {
+    /// Creates a player that uses the given table.
+    pub fn new(tablebase: Tablebase) -> TablebasePlayer {
+        TablebasePlayer { tablebase }
+    }
+}
+
+impl Player for TablebasePlayer {
+    /// # Panics
+    ///
+    /// Panics if the board is not covered by the table.
+    fn take_turn(&mut self, board: &Board) -> Position {
+        let mut best: Option<(Position, Evaluation)> = None;
+        for (position, evaluation) in self.tablebase.evaluate_moves(board) {
+            if best.map_or(true, |(_, best)| evaluation.score() > best.score()) {
+                best = Some((position, evaluation));
+            }
+        }
+        best.expect("The board is not in the tablebase!").0
+    }
+}
+
+#[cfg(test)]
+mod tablebase_tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::optimal::OptimalPlayer;
+    use crate::strategies::RandomPlayer;
+
+    #[test]
+    fn test_statistics() {
+        let statistics = Tablebase::solve().statistics();
+        assert_eq!(
+            statistics,
+            Statistics {
+                positions: 5478,
+                canonical_positions: 765,
+                x_wins: 626,
+                o_wins: 316,
+                draws: 16,
+            }
+        );
+    }
+
+    #[test]
+    fn test_agrees_with_optimal_player() {
+        let tablebase = Tablebase::solve();
+        let mut optimal = OptimalPlayer::new();
+        for (index, entry) in tablebase.entries.iter().enumerate() {
+            if *entry != UNREACHABLE {
+                let board = tablebase.board_at(index);
+                assert_eq!(tablebase.get(&board), Some(optimal.evaluate(&board)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_other_board_sizes() {
+        let tablebase = Tablebase::solve();
+        assert_eq!(tablebase.get(&Board::with_size(3, 4, 3)), None);
+        assert_eq!(tablebase.get(&Board::with_size(3, 3, 2)), None);
+        assert_eq!(tablebase.get(&Board::misere()), None);
+    }
+
+    #[test]
+    fn test_smaller_board() {
+        let tablebase = Tablebase::solve_with_size(2, 2, 2);
+        // X always wins on the third move.
+        assert_eq!(
+            tablebase.get(&Board::with_size(2, 2, 2)),
+            Some(Evaluation::Win(3))
+        );
+        assert_eq!(tablebase.len(), 1 + 4 + 12 + 12);
+    }
+
+    #[test]
+    #[should_panic(expected = "Too many squares for a tablebase: 4x4")]
+    fn test_too_large() {
+        Tablebase::solve_with_size(4, 4, 3);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let tablebase = Tablebase::solve();
+        let mut bytes = Vec::new();
+        tablebase.write_to(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 8 + 19683);
+        let reloaded = Tablebase::read_from(&mut bytes.as_slice()).unwrap();
+        assert_eq!(reloaded, tablebase);
+
+        let path = std::env::temp_dir().join(format!("tablebase-{}.bin", std::process::id()));
+        tablebase.save(&path).unwrap();
+        let loaded = Tablebase::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), tablebase);
+    }
+
+    #[test]
+    fn test_invalid_files() {
+        let mut bytes = Vec::new();
+        Tablebase::solve_with_size(2, 2, 2)
+            .write_to(&mut bytes)
+            .unwrap();
+        let read = |bytes: &[u8]| {
+            Tablebase::read_from(&mut &bytes[..])
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(read(b"TTT"), "Tablebase file is truncated");
+        assert_eq!(read(&bytes[..20]), "Tablebase file is truncated");
+        assert_eq!(read(b"PNG\x01\x01\x02\x02\x02"), "Not a tablebase file");
+        assert_eq!(
+            read(b"TTTB\x07\x02\x02\x02"),
+            "Unsupported tablebase version 7"
+        );
+        assert_eq!(
+            read(b"TTTB\x01\x04\x04\x03"),
+            "Invalid tablebase size: 4x4/3"
+        );
+        let mut extra = bytes.clone();
+        extra.push(0);
+        assert_eq!(read(&extra), "Tablebase file has extra data at the end");
+    }
+
+    #[test]
+    fn test_player_never_loses() {
+        let mut player = TablebasePlayer::new(Tablebase::solve());
+        for seed in 0..50 {
+            let game = Game::new(&mut player, RandomPlayer::with_seed(seed));
+            assert_ne!(game.result(), GameResult::Win(Token::O));
+            let game = Game::new(RandomPlayer::with_seed(seed), &mut player);
+            assert_ne!(game.result(), GameResult::Win(Token::X));
+        }
+    }
+
+    #[test]
+    fn test_player_prefers_fastest_win() {
+        let mut player = TablebasePlayer::new(Tablebase::solve());
+        let board = Board::from_string(
+            "XX-\
+             -O-\
+             --O",
+        );
+        assert_eq!(player.take_turn(&board), Position::new(0, 2));
+    }
+} // mod tablebase_tests
@@ -0,0 +1,590 @@
+//! Ultimate tic-tac-toe: nine boards, arranged in a 3x3 grid, whose results make a tenth.
+//!
+//! The square a player takes, within its board, chooses the board their opponent must play on
+//! next: taking the top right square of any board sends the opponent to the top right board.
+//! If that board is already finished, the opponent may play on any board that is not.
+//! Winning a board claims its place in the grid, and three claimed boards in a row win the game.
+use crate::board::{Board, GameResult, MoveError, Position, Token};
+use crate::record::format_position;
+
+/// A move in ultimate tic-tac-toe: a square on one of the nine boards.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct UltimateMove {
+    board: Position,
+    square: Position,
+}
+
+impl UltimateMove {
+    /// Creates a move on the given square of the given board.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either position is outside a 3x3 grid.
+    pub fn new(board: Position, square: Position) -> UltimateMove {
+        if !Board::new().contains(board) || !Board::new().contains(square) {
+            panic!("Invalid ultimate move: {:?} {:?}", board, square);
+        }
+        UltimateMove { board, square }
+    }
+
+    /// Returns the position, in the grid, of the board the move is played on.
+    pub fn board(&self) -> Position {
+        self.board
+    }
+
+    /// Returns the position of the square within its board.
+    pub fn square(&self) -> Position {
+        self.square
+    }
+
+    /// Returns the position of the square on the full 9x9 grid.
+    pub fn grid_position(&self) -> Position {
+        Position::new(
+            self.board.row() * 3 + self.square.row(),
+            self.board.column() * 3 + self.square.column(),
+        )
+    }
+}
+
+impl std::fmt::Display for UltimateMove {
+    /// Writes the move in coordinate notation on the 9x9 grid, e.g. "e5" for the very center.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", format_position(self.grid_position()))
+    }
+}
+
+/// The ways a move can be against the rules of ultimate tic-tac-toe.
+#[derive(Debug, PartialEq)]
+pub enum UltimateMoveError {
+    /// The game is already over.
+    GameOver,
+    /// The given Token tried to move when it is not their turn.
+    OutOfTurn(Token),
+    /// The move was not on the board the last move sent the player to.
+    WrongBoard { expected: Position, found: Position },
+    /// The board at the given position is already finished.
+    BoardFinished(Position),
+    /// The square cannot be played on its board.
+    Square(MoveError),
+}
+
+impl std::fmt::Display for UltimateMoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UltimateMoveError::GameOver => write!(f, "The game is already over!"),
+            UltimateMoveError::OutOfTurn(player) => write!(f, "It is not {}'s turn!", player),
+            UltimateMoveError::WrongBoard { expected, found } => write!(
+                f,
+                "The move must be on board [{},{}], not [{},{}]!",
+                expected.row(),
+                expected.column(),
+                found.row(),
+                found.column()
+            ),
+            UltimateMoveError::BoardFinished(board) => write!(
+                f,
+                "Board [{},{}] is already finished!",
+                board.row(),
+                board.column()
+            ),
+            UltimateMoveError::Square(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for UltimateMoveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            UltimateMoveError::Square(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// The nine boards of a game of ultimate tic-tac-toe.
+///
+/// # Examples
+/// ```
+/// use tictactoelib::board::{GameResult, Position, Token};
+/// use tictactoelib::ultimate::{UltimateBoard, UltimateMove};
+///
+/// let mut board = UltimateBoard::new();
+/// assert_eq!(board.legal_moves().len(), 81);
+///
+/// // Taking the top right square of the center board sends O to the top right board.
+/// board.add_move(Token::X, UltimateMove::new(Position::new(1, 1), Position::new(0, 2)));
+/// assert_eq!(board.next_board(), Some(Position::new(0, 2)));
+/// assert_eq!(board.legal_moves().len(), 9);
+/// assert_eq!(board.whose_turn(), Some(Token::O));
+/// assert_eq!(board.get_game_result(), GameResult::InProgress);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct UltimateBoard {
+    boards: [Board; 9],
+    /// The boards won so far, marked with their winner's token.
+    /// Its lines are the lines of the grid, so it is won when the game is.
+    grid: Board,
+    /// Every move played, with the board the player was sent to before it.
+    moves: Vec<(UltimateMove, Option<Position>)>,
+    next_board: Option<Position>,
+}
+
+impl Default for UltimateBoard {
+    fn default() -> UltimateBoard {
+        UltimateBoard::new()
+    }
+}
+
+impl UltimateBoard {
+    /// Creates nine empty boards, with X to move anywhere.
+    pub fn new() -> UltimateBoard {
+        UltimateBoard {
+            boards: [Board::new(); 9],
+            grid: Board::new(),
+            moves: Vec::new(),
+            next_board: None,
+        }
+    }
+
+    /// Returns the board at the given position in the grid.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the position is outside the 3x3 grid.
+    pub fn board(&self, position: Position) -> &Board {
+        &self.boards[grid_index(position)]
+    }
+
+    /// Returns the board the next move must be played on,
+    /// or None if it may be played on any unfinished board.
+    pub fn next_board(&self) -> Option<Position> {
+        self.next_board
+    }
+
+    /// Returns whose turn it is, or None if the game is over.
+    pub fn whose_turn(&self) -> Option<Token> {
+        if self.get_game_result() != GameResult::InProgress {
+            None
+        } else if self.moves.len() % 2 == 0 {
+            Some(Token::X)
+        } else {
+            Some(Token::O)
+        }
+    }
+
+    /// Returns every move played, in order.
+    pub fn moves(&self) -> Vec<UltimateMove> {
+        self.moves
+            .iter()
+            .map(|(played, _sent_to)| *played)
+            .collect()
+    }
+
+    /// Returns the most recent move, or None if no moves have been played.
+    pub fn last_move(&self) -> Option<UltimateMove> {
+        self.moves.last().map(|(played, _sent_to)| *played)
+    }
+
+    /// Returns every move the player whose turn it is may make, board by board.
+    pub fn legal_moves(&self) -> Vec<UltimateMove> {
+        if self.whose_turn().is_none() {
+            return Vec::new();
+        }
+        self.playable_boards()
+            .into_iter()
+            .flat_map(|board| {
+                self.board(board)
+                    .empty_positions_iter()
+                    .map(move |square| UltimateMove { board, square })
+                    .collect::<Vec<UltimateMove>>()
+            })
+            .collect()
+    }
+
+    /// Returns the boards the next move may be played on.
+    fn playable_boards(&self) -> Vec<Position> {
+        match self.next_board {
+            Some(board) => vec![board],
+            None => Board::new()
+                .empty_positions_iter()
+                .filter(|board| self.board(*board).get_game_result() == GameResult::InProgress)
+                .collect(),
+        }
+    }
+
+    /// Plays the move for the given player.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the move is not allowed; see try_add_move().
+    pub fn add_move(&mut self, player: Token, at: UltimateMove) {
+        self.try_add_move(player, at)
+            .unwrap_or_else(|error| panic!("{}", error));
+    }
+
+    /// Plays the move for the given player,
+    /// or returns an UltimateMoveError, leaving the boards unchanged, if the move is not allowed.
+    ///
+    /// # Examples
+    /// ```
+    /// use tictactoelib::board::{Position, Token};
+    /// use tictactoelib::ultimate::{UltimateBoard, UltimateMove, UltimateMoveError};
+    ///
+    /// let mut board = UltimateBoard::new();
+    /// let center = Position::new(1, 1);
+    /// assert_eq!(board.try_add_move(Token::X, UltimateMove::new(center, center)), Ok(()));
+    /// // X sent O back to the center board.
+    /// assert_eq!(
+    ///     board.try_add_move(Token::O, UltimateMove::new(Position::new(0, 0), center)),
+    ///     Err(UltimateMoveError::WrongBoard { expected: center, found: Position::new(0, 0) })
+    /// );
+    /// ```
+    pub fn try_add_move(
+        &mut self,
+        player: Token,
+        at: UltimateMove,
+    ) -> Result<(), UltimateMoveError> {
+        match self.whose_turn() {
+            None => return Err(UltimateMoveError::GameOver),
+            Some(whose_turn) if whose_turn != player => {
+                return Err(UltimateMoveError::OutOfTurn(player))
+            }
+            Some(_) => {}
+        }
+        if let Some(expected) = self.next_board {
+            if at.board != expected {
+                return Err(UltimateMoveError::WrongBoard {
+                    expected,
+                    found: at.board,
+                });
+            }
+        }
+        let board = &mut self.boards[grid_index(at.board)];
+        match board.try_place(player, at.square) {
+            Ok(()) => {}
+            Err(MoveError::GameOver) => return Err(UltimateMoveError::BoardFinished(at.board)),
+            Err(error) => return Err(UltimateMoveError::Square(error)),
+        }
+        if board.get_game_result() == GameResult::Win(player) {
+            self.grid
+                .try_place(player, at.board)
+                .expect("A finished board cannot be won again!");
+        }
+
+        self.moves.push((at, self.next_board));
+        let sent_to = self.board(at.square);
+        self.next_board = if sent_to.get_game_result() == GameResult::InProgress {
+            Some(at.square)
+        } else {
+            None
+        };
+        Ok(())
+    }
+
+    /// Takes back the most recent move, returning it,
+    /// or None if no moves have been played.
+    pub fn undo_move(&mut self) -> Option<UltimateMove> {
+        let (played, sent_to) = self.moves.pop()?;
+        let board = &mut self.boards[grid_index(played.board)];
+        if let GameResult::Win(_winner) = board.get_game_result() {
+            // The move won the board, so it was the last one marked on the grid.
+            self.grid.undo_move();
+        }
+        board.undo_move();
+        self.next_board = sent_to;
+        Some(played)
+    }
+
+    /// Gets the result of the game.
+    ///
+    /// A player who wins three boards in a line, across, down or diagonally, wins the game.
+    /// If every board is finished without that, the game is drawn.
+    pub fn get_game_result(&self) -> GameResult {
+        match self.grid.get_game_result() {
+            GameResult::Win(winner) => GameResult::Win(winner),
+            _ if self.playable_boards().is_empty() => GameResult::Draw,
+            _ => GameResult::InProgress,
+        }
+    }
+}
+
+impl std::fmt::Display for UltimateBoard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Will look something like:
+        // ┌───┬───┬───┐
+        // │X  │   │ O │
+        // │ O │ X │   │
+        // │   │   │   │
+        // ├───┼───┼───┤
+        // ...
+        // └───┴───┴───┘
+        writeln!(f, "┌───┬───┬───┐")?;
+        for row in 0..9 {
+            if row > 0 && row % 3 == 0 {
+                writeln!(f, "├───┼───┼───┤")?;
+            }
+            write!(f, "│")?;
+            for column in 0..9 {
+                let board = self.board(Position::new(row / 3, column / 3));
+                match board.token_at(Position::new(row % 3, column % 3)) {
+                    Some(token) => write!(f, "{}", token)?,
+                    None => write!(f, " ")?,
+                }
+                if column % 3 == 2 {
+                    write!(f, "│")?;
+                }
+            }
+            writeln!(f)?;
+        }
+        write!(f, "└───┴───┴───┘")
+    }
+}
+
+fn grid_index(position: Position) -> usize {
+    if position.row() >= 3 || position.column() >= 3 {
+        panic!(
+            "Position [{},{}] is out of range!",
+            position.row(),
+            position.column()
+        );
+    }
+    position.row() as usize * 3 + position.column() as usize
+}
+
+/// A player of ultimate tic-tac-toe.
+pub trait UltimatePlayer {
+    /// Implement take_turn() to return the desired move, given an in-progress game.
+    fn take_turn(&mut self, board: &UltimateBoard) -> UltimateMove;
+}
+
+/// A borrowed player can play too, so that one player can be used for several games.
+impl<P: UltimatePlayer + ?Sized> UltimatePlayer for &mut P {
+    fn take_turn(&mut self, board: &UltimateBoard) -> UltimateMove {
+        (**self).take_turn(board)
+    }
+}
+
+/// A boxed player can play too, so that the kind of player can be chosen at runtime.
+impl<P: UltimatePlayer + ?Sized> UltimatePlayer for Box<P> {
+    fn take_turn(&mut self, board: &UltimateBoard) -> UltimateMove {
+        (**self).take_turn(board)
+    }
+}
+
+/// A single game of ultimate tic-tac-toe between two players.
+///
+/// # Examples
+/// ```
+/// use tictactoelib::board::GameResult;
+/// use tictactoelib::strategies::RandomPlayer;
+/// use tictactoelib::ultimate::UltimateGame;
+///
+/// let mut game = UltimateGame::new(RandomPlayer::with_seed(1), RandomPlayer::with_seed(2));
+/// assert_ne!(game.play_to_end(), GameResult::InProgress);
+/// ```
+pub struct UltimateGame<X: UltimatePlayer, O: UltimatePlayer> {
+    board: UltimateBoard,
+    x: X,
+    o: O,
+}
+
+impl<X: UltimatePlayer, O: UltimatePlayer> UltimateGame<X, O> {
+    /// Creates a game between two players, without playing any moves.
+    pub fn new(x: X, o: O) -> UltimateGame<X, O> {
+        UltimateGame {
+            board: UltimateBoard::new(),
+            x,
+            o,
+        }
+    }
+
+    /// Asks the player whose turn it is for a move, and plays it.
+    ///
+    /// Returns the move played, or None if the game was already over.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the player chooses a move that is not allowed.
+    pub fn step(&mut self) -> Option<UltimateMove> {
+        let whose_turn = self.board.whose_turn()?;
+        let their_move = match whose_turn {
+            Token::X => self.x.take_turn(&self.board),
+            Token::O => self.o.take_turn(&self.board),
+        };
+        self.board.add_move(whose_turn, their_move);
+        Some(their_move)
+    }
+
+    /// Plays moves until the game is over, and returns the result.
+    pub fn play_to_end(&mut self) -> GameResult {
+        while self.step().is_some() {}
+        self.result()
+    }
+
+    /// Returns the boards, as they stand.
+    pub fn board(&self) -> &UltimateBoard {
+        &self.board
+    }
+
+    /// Returns the result of the game.
+    pub fn result(&self) -> GameResult {
+        self.board.get_game_result()
+    }
+}
+
+#[cfg(test)]
+mod ultimate_tests {
+    use super::*;
+    use crate::strategies::RandomPlayer;
+
+    fn at(board: u8, square: u8) -> UltimateMove {
+        // Boards and squares are numbered 1-9, as the HumanPlayer asks for them.
+        let position = |number: u8| Position::new((number - 1) / 3, (number - 1) % 3);
+        UltimateMove::new(position(board), position(square))
+    }
+
+    fn play(moves: &[(u8, u8)]) -> UltimateBoard {
+        let mut board = UltimateBoard::new();
+        for (board_number, square) in moves {
+            let player = board.whose_turn().unwrap();
+            board.add_move(player, at(*board_number, *square));
+        }
+        board
+    }
+
+    #[test]
+    fn test_send_rule() {
+        let mut board = play(&[(5, 1)]);
+        assert_eq!(board.next_board(), Some(Position::new(0, 0)));
+        assert!(board
+            .legal_moves()
+            .iter()
+            .all(|legal| legal.board() == Position::new(0, 0)));
+        assert_eq!(
+            board.try_add_move(Token::O, at(2, 1)),
+            Err(UltimateMoveError::WrongBoard {
+                expected: Position::new(0, 0),
+                found: Position::new(0, 1)
+            })
+        );
+        assert_eq!(
+            board.try_add_move(Token::X, at(1, 1)),
+            Err(UltimateMoveError::OutOfTurn(Token::X))
+        );
+        assert_eq!(board.try_add_move(Token::O, at(1, 5)), Ok(()));
+        // O sent X back to the center board, where X has already taken the top left.
+        assert_eq!(
+            board.try_add_move(Token::X, at(5, 1)),
+            Err(UltimateMoveError::Square(MoveError::Occupied(
+                Position::new(0, 0)
+            )))
+        );
+        assert_eq!(board.legal_moves().len(), 8);
+    }
+
+    #[test]
+    fn test_free_move_after_finished_board() {
+        // X wins the center board with its top row.
+        let mut board = play(&[(5, 1), (1, 5), (5, 2), (2, 5), (5, 3)]);
+        assert_eq!(
+            board.board(Position::new(1, 1)).get_game_result(),
+            GameResult::Win(Token::X)
+        );
+        // X sent O to the top right board, which is still in play.
+        assert_eq!(board.next_board(), Some(Position::new(0, 2)));
+        board.add_move(Token::O, at(3, 5));
+        // O sent X to the finished center board, so X may play on any other board.
+        assert_eq!(board.next_board(), None);
+        let legal_moves = board.legal_moves();
+        assert!(legal_moves
+            .iter()
+            .all(|legal| legal.board() != Position::new(1, 1)));
+        assert_eq!(legal_moves.len(), 81 - 9 - 3);
+        assert_eq!(
+            board.try_add_move(Token::X, at(5, 4)),
+            Err(UltimateMoveError::BoardFinished(Position::new(1, 1)))
+        );
+    }
+
+    #[test]
+    fn test_random_games_finish() {
+        for seed in 0..20 {
+            let mut game = UltimateGame::new(
+                RandomPlayer::with_seed(seed),
+                RandomPlayer::with_seed(seed + 100),
+            );
+            let result = game.play_to_end();
+            let board = game.board();
+            assert_eq!(board.whose_turn(), None);
+            assert!(board.legal_moves().is_empty());
+
+            // The game is won by a line of won boards, or drawn when every board is finished.
+            let won_line = |token: Token| {
+                Board::new().lines().any(|line| {
+                    line.positions().all(|position| {
+                        board.board(position).get_game_result() == GameResult::Win(token)
+                    })
+                })
+            };
+            match result {
+                GameResult::Win(winner) => {
+                    assert!(won_line(winner));
+                    assert!(!won_line(winner.opponent()));
+                    let last_player = if board.moves().len() % 2 == 0 {
+                        Token::O
+                    } else {
+                        Token::X
+                    };
+                    assert_eq!(winner, last_player);
+                }
+                GameResult::Draw => {
+                    assert!(!won_line(Token::X) && !won_line(Token::O));
+                    assert!(Board::new().empty_positions_iter().all(|position| {
+                        board.board(position).get_game_result() != GameResult::InProgress
+                    }));
+                }
+                GameResult::InProgress => panic!("Game should be over!"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_undo() {
+        let mut game = UltimateGame::new(RandomPlayer::with_seed(3), RandomPlayer::with_seed(4));
+        let mut history = vec![game.board().clone()];
+        while game.step().is_some() {
+            history.push(game.board().clone());
+        }
+        let mut board = history.pop().unwrap();
+        while let Some(before) = history.pop() {
+            assert!(board.undo_move().is_some());
+            assert_eq!(board, before);
+        }
+        assert_eq!(board, UltimateBoard::new());
+        assert_eq!(board.undo_move(), None);
+    }
+
+    #[test]
+    fn test_display() {
+        let board = play(&[(5, 1), (1, 5), (5, 9)]);
+        assert_eq!(
+            board.to_string(),
+            "┌───┬───┬───┐\n\
+             │   │   │   │\n\
+             │ O │   │   │\n\
+             │   │   │   │\n\
+             ├───┼───┼───┤\n\
+             │   │X  │   │\n\
+             │   │   │   │\n\
+             │   │  X│   │\n\
+             ├───┼───┼───┤\n\
+             │   │   │   │\n\
+             │   │   │   │\n\
+             │   │   │   │\n\
+             └───┴───┴───┘"
+        );
+        assert_eq!(at(5, 5).to_string(), "e5");
+        assert_eq!(at(3, 3).to_string(), "i1");
+        assert_eq!(at(7, 7).to_string(), "a9");
+    }
+} // mod ultimate_tests
@@ -0,0 +1,249 @@
+//! Exhaustive verification that a player never loses.
+//!
+//! Rather than sampling games, the verifier plays every possible sequence of opponent moves
+//! against the player, as both X and O, and reports every line of play that it loses.
+//! The player must be deterministic, so that it is asked about each position only once.
+use crate::board::{Board, GameResult, Position, Token};
+use crate::player::Player;
+use crate::record::format_position;
+
+/// A sequence of moves, from the starting board, that ends with the verified player losing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LosingLine {
+    token: Token,
+    moves: Vec<Position>,
+}
+
+impl LosingLine {
+    /// Returns the token the verified player was playing.
+    pub fn token(&self) -> Token {
+        self.token
+    }
+
+    /// Returns every move of the game, by both players, in order.
+    pub fn moves(&self) -> &[Position] {
+        &self.moves
+    }
+}
+
+impl std::fmt::Display for LosingLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let moves: Vec<String> = self
+            .moves
+            .iter()
+            .map(|position| format_position(*position))
+            .collect();
+        write!(f, "{} loses after {}", self.token, moves.join(" "))
+    }
+}
+
+/// The results of verifying a player.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Verification {
+    games_as_x: u64,
+    games_as_o: u64,
+    losing_lines: Vec<LosingLine>,
+}
+
+impl Verification {
+    /// Returns the number of different games played with the player as X.
+    pub fn games_as_x(&self) -> u64 {
+        self.games_as_x
+    }
+
+    /// Returns the number of different games played with the player as O.
+    pub fn games_as_o(&self) -> u64 {
+        self.games_as_o
+    }
+
+    /// Returns every game the player lost, as X and then as O.
+    pub fn losing_lines(&self) -> &[LosingLine] {
+        &self.losing_lines
+    }
+
+    /// Returns whether the player did not lose a single game.
+    pub fn never_loses(&self) -> bool {
+        self.losing_lines.is_empty()
+    }
+}
+
+impl std::fmt::Display for Verification {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "Games as X: {}", self.games_as_x)?;
+        writeln!(f, "Games as O: {}", self.games_as_o)?;
+        writeln!(f, "Losses:     {}", self.losing_lines.len())?;
+        for line in &self.losing_lines {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Plays every possible sequence of opponent moves against the player, from an empty board.
+///
+/// # Examples
+/// ```
+/// use tictactoelib::board::{Board, Position};
+/// use tictactoelib::optimal::OptimalPlayer;
+/// use tictactoelib::player::Player;
+/// use tictactoelib::verification;
+///
+/// let verification = verification::verify(OptimalPlayer::new());
+/// assert!(verification.never_loses());
+///
+/// // A player that always takes the first empty square is easily beaten.
+/// struct FirstEmpty;
+/// impl Player for FirstEmpty {
+///     fn take_turn(&mut self, board: &Board) -> Position {
+///         board.empty_positions()[0]
+///     }
+/// }
+/// let verification = verification::verify(FirstEmpty);
+/// assert!(!verification.never_loses());
+/// ```
+pub fn verify(player: impl Player) -> Verification {
+    verify_from(player, Board::new())
+}
+
+/// Plays every possible sequence of opponent moves against the player, from the given board.
+///
+/// The player plays each side in turn from the starting board,
+/// so it is verified as X and as O whoever is to move.
+pub fn verify_from(mut player: impl Player, start: Board) -> Verification {
+    let mut verification = Verification::default();
+    let mut moves = Vec::new();
+    let mut board = start;
+    verification.games_as_x = explore(
+        &mut player,
+        Token::X,
+        &mut board,
+        &mut moves,
+        &mut verification.losing_lines,
+    );
+    let mut board = start;
+    verification.games_as_o = explore(
+        &mut player,
+        Token::O,
+        &mut board,
+        &mut moves,
+        &mut verification.losing_lines,
+    );
+    verification
+}
+
+/// Plays out every game from the board, and returns how many there were.
+fn explore(
+    player: &mut impl Player,
+    token: Token,
+    board: &mut Board,
+    moves: &mut Vec<Position>,
+    losing_lines: &mut Vec<LosingLine>,
+) -> u64 {
+    match board.get_game_result() {
+        GameResult::Win(winner) => {
+            if winner != token {
+                losing_lines.push(LosingLine {
+                    token,
+                    moves: moves.clone(),
+                });
+            }
+            return 1;
+        }
+        GameResult::Draw => return 1,
+        GameResult::InProgress => {}
+    }
+
+    let turn = board.whose_turn().expect("Game should not be over!");
+    let choices = if turn == token {
+        vec![player.take_turn(board)]
+    } else {
+        board.empty_positions()
+    };
+    let mut games = 0;
+    for position in choices {
+        board.add_move(turn, position);
+        moves.push(position);
+        games += explore(player, token, board, moves, losing_lines);
+        moves.pop();
+        board.undo_move();
+    }
+    games
+}
+
+#[cfg(test)]
+mod verification_tests {
+    use super::*;
+
+    /// Always takes the first empty square.
+    struct FirstEmpty;
+
+    impl Player for FirstEmpty {
+        fn take_turn(&mut self, board: &Board) -> Position {
+            board.empty_positions()[0]
+        }
+    }
+
+    #[test]
+    fn test_finds_losing_lines() {
+        let verification = verify(FirstEmpty);
+        assert!(!verification.never_loses());
+        assert_gt!(verification.games_as_x(), 0);
+        assert_gt!(verification.games_as_o(), 0);
+
+        for line in verification.losing_lines() {
+            // Replaying the line ends in a win for the opponent.
+            let mut board = Board::new();
+            for position in line.moves() {
+                board.add_move(board.whose_turn().unwrap(), *position);
+            }
+            assert_eq!(
+                board.get_game_result(),
+                GameResult::Win(line.token().opponent())
+            );
+        }
+        let tokens: Vec<Token> = verification
+            .losing_lines()
+            .iter()
+            .map(|line| line.token())
+            .collect();
+        assert!(tokens.contains(&Token::X));
+        assert!(tokens.contains(&Token::O));
+    }
+
+    #[test]
+    fn test_display() {
+        let start = Board::from_string(
+            "XOX\
+             OO-\
+             X--",
+        );
+        let verification = verify_from(FirstEmpty, start);
+        // As X, blocking on c2 leaves O to win on b3 or draw on c3.
+        // As O, whatever X does, the first empty square wins.
+        assert_eq!(verification.games_as_x(), 2);
+        assert_eq!(verification.games_as_o(), 3);
+        assert_eq!(verification.losing_lines().len(), 1);
+        assert_eq!(
+            verification.losing_lines()[0].to_string(),
+            "X loses after c2 b3"
+        );
+        assert_eq!(
+            verification.to_string(),
+            "Games as X: 2\nGames as O: 3\nLosses:     1\nX loses after c2 b3\n"
+        );
+    }
+
+    #[test]
+    fn test_game_over() {
+        let start = Board::from_string(
+            "XXX\
+             OO-\
+             ---",
+        );
+        let verification = verify_from(FirstEmpty, start);
+        assert_eq!(verification.games_as_x(), 1);
+        assert_eq!(verification.games_as_o(), 1);
+        assert_eq!(verification.losing_lines().len(), 1);
+        assert_eq!(verification.losing_lines()[0].token(), Token::O);
+    }
+} // mod verification_tests